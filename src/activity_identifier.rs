@@ -1,8 +1,15 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
-use abi_stable::std_types::ROption;
+use abi_stable::std_types::{RHashMap, ROption, RString, Tuple2};
 
-use crate::module::{ActivityIdentifier, ActivityMetadata};
+use crate::{
+    condition::Condition,
+    geometry::SizeAbi,
+    localization::LocalizedString,
+    metadata::{self, MetadataNamespace, MetadataValue, PlacementHint, SizeHints},
+    module::{ActivityIdentifier, ActivityMetadata, UIServerCommand},
+    web_content::WebContentPolicy,
+};
 
 impl ActivityIdentifier {
     pub fn new(module_name: &str, activity_name: &str) -> Self {
@@ -12,6 +19,36 @@ impl ActivityIdentifier {
             metadata: ActivityMetadata::default(),
         }
     }
+    /// Create an identifier checking the names with [`validate_name`]
+    pub fn try_new(module_name: &str, activity_name: &str) -> Result<Self, NameError> {
+        validate_name(NameField::Module, module_name)?;
+        validate_name(NameField::Activity, activity_name)?;
+        Ok(Self::new(module_name, activity_name))
+    }
+    /// Check the names with [`validate_name`], hosts can use it to refuse invalid activities
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+    pub fn validate(&self) -> Result<(), NameError> {
+        validate_name(NameField::Module, &self.module)?;
+        validate_name(NameField::Activity, &self.activity)
+    }
+    /// Build an identifier with its metadata in one expression
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let id = ActivityIdentifier::builder(NAME, "player")
+    ///     .window_name("top")
+    ///     .meta("artist", "someone")
+    ///     .build();
+    /// ```
+    pub fn builder(module_name: &str, activity_name: &str) -> ActivityIdentifierBuilder {
+        ActivityIdentifierBuilder {
+            module: module_name.into(),
+            activity: activity_name.into(),
+            metadata: ActivityMetadataBuilder::default(),
+        }
+    }
     pub fn module(&self) -> &str {
         &self.module
     }
@@ -30,12 +67,39 @@ impl ActivityIdentifier {
     pub fn set_metadata(&mut self, metadata: ActivityMetadata) {
         self.metadata = metadata;
     }
+
+    /// The same identifier with another activity name, the metadata is kept
+    pub fn renamed(&self, new_name: &str) -> Self {
+        Self {
+            module: self.module.clone(),
+            activity: new_name.into(),
+            metadata: self.metadata.clone(),
+        }
+    }
+
+    /// Name to show to the user for `locale`, falls back to the activity name
+    pub fn display_name(&self, locale: &str) -> String {
+        match &self.metadata.display_name {
+            ROption::RSome(name) => name.resolve(locale).to_string(),
+            ROption::RNone => self.activity.to_string(),
+        }
+    }
+    /// Name to show to the user for the locale of the current process, falls back to the activity name
+    pub fn current_display_name(&self) -> String {
+        match &self.metadata.display_name {
+            ROption::RSome(name) => name.resolve_current().to_string(),
+            ROption::RNone => self.activity.to_string(),
+        }
+    }
 }
 
 impl ActivityMetadata {
     pub fn new() -> Self {
         Self::default()
     }
+    pub fn builder() -> ActivityMetadataBuilder {
+        ActivityMetadataBuilder::default()
+    }
     pub fn set_window_name(&mut self, window_name: &str) {
         self.window_name = ROption::RSome(window_name.to_string().into());
     }
@@ -45,6 +109,58 @@ impl ActivityMetadata {
             ROption::RNone => None,
         }
     }
+    pub fn set_display_name(&mut self, display_name: LocalizedString) {
+        self.display_name = ROption::RSome(display_name);
+    }
+    pub fn display_name(&self) -> Option<LocalizedString> {
+        match &self.display_name {
+            ROption::RSome(name) => Some(name.clone()),
+            ROption::RNone => None,
+        }
+    }
+    pub fn set_web_content_policy(&mut self, policy: WebContentPolicy) {
+        self.web_content_policy = ROption::RSome(policy);
+    }
+    pub fn web_content_policy(&self) -> Option<&WebContentPolicy> {
+        match &self.web_content_policy {
+            ROption::RSome(policy) => Some(policy),
+            ROption::RNone => None,
+        }
+    }
+    pub fn set_visibility_condition(&mut self, condition: Condition) {
+        self.visibility_condition = ROption::RSome(condition);
+    }
+    pub fn visibility_condition(&self) -> Option<&Condition> {
+        match &self.visibility_condition {
+            ROption::RSome(condition) => Some(condition),
+            ROption::RNone => None,
+        }
+    }
+    pub fn set_size_hints(&mut self, hints: SizeHints) {
+        self.size_hints = ROption::RSome(hints);
+    }
+    pub fn size_hints(&self) -> Option<&SizeHints> {
+        match &self.size_hints {
+            ROption::RSome(hints) => Some(hints),
+            ROption::RNone => None,
+        }
+    }
+    pub fn set_placement(&mut self, placement: PlacementHint) {
+        self.placement = ROption::RSome(placement);
+    }
+    pub fn placement(&self) -> Option<&PlacementHint> {
+        match &self.placement {
+            ROption::RSome(placement) => Some(placement),
+            ROption::RNone => None,
+        }
+    }
+    /// Whether the activity should be shown, true if there is no visibility condition
+    pub fn is_visible(&self) -> bool {
+        match &self.visibility_condition {
+            ROption::RSome(condition) => condition.evaluate_metadata(self),
+            ROption::RNone => true,
+        }
+    }
     pub fn set_additional_metadata(&mut self, key: String, value: String) {
         self.additional_metadata.insert(key.into(), value.into());
     }
@@ -54,6 +170,113 @@ impl ActivityMetadata {
             Option::None => None,
         }
     }
+    /// Set the entry `name` of `namespace`, see [`MetadataNamespace`]
+    pub fn set_namespaced_metadata(
+        &mut self,
+        namespace: MetadataNamespace,
+        name: &str,
+        value: &str,
+    ) {
+        self.additional_metadata
+            .insert(namespace.key(name).into(), value.into());
+    }
+    pub fn namespaced_metadata(&self, namespace: MetadataNamespace, name: &str) -> Option<String> {
+        self.additional_metadata(&namespace.key(name))
+    }
+    pub fn remove_namespaced_metadata(&mut self, namespace: MetadataNamespace, name: &str) {
+        self.additional_metadata
+            .remove(namespace.key(name).as_str());
+    }
+    /// Entries of `namespace` with their names without the prefix, sorted by name
+    pub fn namespace_entries(&self, namespace: MetadataNamespace) -> Vec<(String, String)> {
+        let mut entries: Vec<_> = self
+            .additional_metadata
+            .iter()
+            .filter_map(|Tuple2(key, value)| {
+                let name = key.strip_prefix(namespace.prefix())?;
+                Some((name.to_string(), value.to_string()))
+            })
+            .collect();
+        entries.sort();
+        entries
+    }
+    /// Set a typed value, it doesn't change the string entry with the same key
+    pub fn set_value(&mut self, key: &str, value: impl Into<MetadataValue>) {
+        self.typed_metadata.insert(key.into(), value.into());
+    }
+    pub fn value(&self, key: &str) -> Option<&MetadataValue> {
+        self.typed_metadata.get(key)
+    }
+    pub fn remove_value(&mut self, key: &str) -> Option<MetadataValue> {
+        self.typed_metadata.remove(key).into()
+    }
+    /// The typed value of `key` if it's a string
+    pub fn string_value(&self, key: &str) -> Option<&str> {
+        self.value(key)?.as_str()
+    }
+    /// The typed value of `key` if it's an int
+    pub fn int_value(&self, key: &str) -> Option<i64> {
+        self.value(key)?.as_int()
+    }
+    /// The typed value of `key` if it's a float or an int
+    pub fn float_value(&self, key: &str) -> Option<f64> {
+        self.value(key)?.as_float()
+    }
+    /// The typed value of `key` if it's a bool
+    pub fn bool_value(&self, key: &str) -> Option<bool> {
+        self.value(key)?.as_bool()
+    }
+    /// The typed value of `key` if it's a string list
+    pub fn string_list_value(&self, key: &str) -> Option<&[RString]> {
+        self.value(key)?.as_string_list()
+    }
+    pub fn set_order_weight(&mut self, weight: i32) {
+        self.set_value(metadata::KEY_ORDER_WEIGHT, weight as i64);
+    }
+    /// See [`metadata::KEY_ORDER_WEIGHT`]
+    pub fn order_weight(&self) -> Option<i32> {
+        self.int_value(metadata::KEY_ORDER_WEIGHT)?.try_into().ok()
+    }
+    /// Set the natural width and height of the [`SizeHints`], keeping the other hints
+    pub fn set_preferred_size(&mut self, size: SizeAbi) {
+        let mut hints = self.size_hints().copied().unwrap_or_default();
+        hints.natural_width = ROption::RSome(size.width);
+        hints.natural_height = ROption::RSome(size.height);
+        self.set_size_hints(hints);
+    }
+    /// The natural size of the [`SizeHints`], `None` unless both the width and the height are set
+    pub fn preferred_size(&self) -> Option<SizeAbi> {
+        let hints = self.size_hints()?;
+        Some(SizeAbi {
+            width: hints.natural_width.into_option()?,
+            height: hints.natural_height.into_option()?,
+        })
+    }
+    pub fn set_icon_name(&mut self, icon_name: &str) {
+        self.set_value(metadata::KEY_ICON_NAME, icon_name);
+    }
+    /// See [`metadata::KEY_ICON_NAME`]
+    pub fn icon_name(&self) -> Option<&str> {
+        self.string_value(metadata::KEY_ICON_NAME)
+    }
+    pub fn set_exclusive(&mut self, exclusive: bool) {
+        self.set_value(metadata::KEY_EXCLUSIVE, exclusive);
+    }
+    /// See [`metadata::KEY_EXCLUSIVE`], false if not set
+    pub fn is_exclusive(&self) -> bool {
+        self.bool_value(metadata::KEY_EXCLUSIVE).unwrap_or(false)
+    }
+    /// Apply a bulk update with the semantics of `UIServerCommand::UpdateMetadataBulk`:
+    /// the keys in `remove` are removed first, then the keys in `set` are inserted,
+    /// so a key present in both ends up set
+    pub fn apply_bulk_update(&mut self, set: &RHashMap<RString, RString>, remove: &[RString]) {
+        for key in remove {
+            self.additional_metadata.remove(key);
+        }
+        for Tuple2(key, value) in set {
+            self.additional_metadata.insert(key.clone(), value.clone());
+        }
+    }
 }
 
 impl Display for ActivityIdentifier {
@@ -61,3 +284,255 @@ impl Display for ActivityIdentifier {
         write!(f, "{}@{}", self.activity, self.module)
     }
 }
+
+/// Maximum length in bytes of a module or activity name
+pub const MAX_NAME_LEN: usize = 128;
+
+/// Which name of an identifier is invalid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameField {
+    Module,
+    Activity,
+}
+
+/// Why a name is invalid, see [`validate_name`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameErrorKind {
+    /// The name is empty or contains only whitespace
+    Empty,
+    TooLong {
+        len: usize,
+    },
+    /// `@` or a control character
+    InvalidChar(char),
+}
+
+/// Error returned by [`ActivityIdentifier::try_new`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameError {
+    pub field: NameField,
+    pub name: String,
+    pub kind: NameErrorKind,
+}
+
+impl Display for NameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let field = match self.field {
+            NameField::Module => "module",
+            NameField::Activity => "activity",
+        };
+        write!(f, "invalid {field} name {:?}: ", self.name)?;
+        match &self.kind {
+            NameErrorKind::Empty => write!(f, "the name is empty"),
+            NameErrorKind::TooLong { len } => {
+                write!(
+                    f,
+                    "the name is {len} bytes long, the maximum is {MAX_NAME_LEN}"
+                )
+            }
+            NameErrorKind::InvalidChar(c) => write!(f, "the name contains {c:?}"),
+        }
+    }
+}
+impl std::error::Error for NameError {}
+
+/// Check a module or activity name: it must contain something other than whitespace,
+/// be at most [`MAX_NAME_LEN`] bytes long and not contain `@` (the separator of the `Display` format)
+/// or control characters
+pub fn validate_name(field: NameField, name: &str) -> Result<(), NameError> {
+    let error = |kind| {
+        Err(NameError {
+            field,
+            name: name.to_string(),
+            kind,
+        })
+    };
+    if name.trim().is_empty() {
+        return error(NameErrorKind::Empty);
+    }
+    if name.len() > MAX_NAME_LEN {
+        return error(NameErrorKind::TooLong { len: name.len() });
+    }
+    if let Some(c) = name.chars().find(|c| *c == '@' || c.is_control()) {
+        return error(NameErrorKind::InvalidChar(c));
+    }
+    Ok(())
+}
+
+/// Error returned when parsing an `activity@module` string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseActivityIdentifierError {
+    /// There is no `@` between the activity and the module name
+    MissingSeparator,
+    InvalidName(NameError),
+}
+
+impl Display for ParseActivityIdentifierError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseActivityIdentifierError::MissingSeparator => {
+                write!(f, "expected an identifier like activity@module")
+            }
+            ParseActivityIdentifierError::InvalidName(err) => write!(f, "{err}"),
+        }
+    }
+}
+impl std::error::Error for ParseActivityIdentifierError {}
+
+/// Parse the `activity@module` format produced by `Display`, the metadata is empty.
+///
+/// Both names must be valid, see [`validate_name`]
+impl FromStr for ActivityIdentifier {
+    type Err = ParseActivityIdentifierError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (activity, module) = s
+            .rsplit_once('@')
+            .ok_or(ParseActivityIdentifierError::MissingSeparator)?;
+        ActivityIdentifier::try_new(module, activity)
+            .map_err(ParseActivityIdentifierError::InvalidName)
+    }
+}
+
+/// Builder for [`ActivityMetadata`], see [`ActivityMetadata::builder`]
+#[derive(Debug, Clone, Default)]
+pub struct ActivityMetadataBuilder {
+    metadata: ActivityMetadata,
+}
+
+impl ActivityMetadataBuilder {
+    pub fn window_name(mut self, window_name: &str) -> Self {
+        self.metadata.set_window_name(window_name);
+        self
+    }
+    pub fn display_name(mut self, display_name: LocalizedString) -> Self {
+        self.metadata.set_display_name(display_name);
+        self
+    }
+    /// Set a string entry of the additional metadata
+    pub fn meta(mut self, key: &str, value: &str) -> Self {
+        self.metadata
+            .set_additional_metadata(key.to_string(), value.to_string());
+        self
+    }
+    /// Set a typed value, see [`ActivityMetadata::set_value`]
+    pub fn value(mut self, key: &str, value: impl Into<MetadataValue>) -> Self {
+        self.metadata.set_value(key, value);
+        self
+    }
+    pub fn web_content_policy(mut self, policy: WebContentPolicy) -> Self {
+        self.metadata.set_web_content_policy(policy);
+        self
+    }
+    pub fn visibility_condition(mut self, condition: Condition) -> Self {
+        self.metadata.set_visibility_condition(condition);
+        self
+    }
+    pub fn size_hints(mut self, hints: SizeHints) -> Self {
+        self.metadata.set_size_hints(hints);
+        self
+    }
+    pub fn placement(mut self, placement: PlacementHint) -> Self {
+        self.metadata.set_placement(placement);
+        self
+    }
+    pub fn build(self) -> ActivityMetadata {
+        self.metadata
+    }
+}
+
+/// Builder for [`ActivityIdentifier`], see [`ActivityIdentifier::builder`]
+#[derive(Debug, Clone)]
+pub struct ActivityIdentifierBuilder {
+    module: RString,
+    activity: RString,
+    metadata: ActivityMetadataBuilder,
+}
+
+impl ActivityIdentifierBuilder {
+    pub fn window_name(mut self, window_name: &str) -> Self {
+        self.metadata = self.metadata.window_name(window_name);
+        self
+    }
+    pub fn display_name(mut self, display_name: LocalizedString) -> Self {
+        self.metadata = self.metadata.display_name(display_name);
+        self
+    }
+    /// Set a string entry of the additional metadata
+    pub fn meta(mut self, key: &str, value: &str) -> Self {
+        self.metadata = self.metadata.meta(key, value);
+        self
+    }
+    /// Set a typed value, see [`ActivityMetadata::set_value`]
+    pub fn value(mut self, key: &str, value: impl Into<MetadataValue>) -> Self {
+        self.metadata = self.metadata.value(key, value);
+        self
+    }
+    pub fn web_content_policy(mut self, policy: WebContentPolicy) -> Self {
+        self.metadata = self.metadata.web_content_policy(policy);
+        self
+    }
+    pub fn visibility_condition(mut self, condition: Condition) -> Self {
+        self.metadata = self.metadata.visibility_condition(condition);
+        self
+    }
+    pub fn size_hints(mut self, hints: SizeHints) -> Self {
+        self.metadata = self.metadata.size_hints(hints);
+        self
+    }
+    pub fn placement(mut self, placement: PlacementHint) -> Self {
+        self.metadata = self.metadata.placement(placement);
+        self
+    }
+    /// Replace all the metadata set until now
+    pub fn metadata(mut self, metadata: ActivityMetadata) -> Self {
+        self.metadata = ActivityMetadataBuilder { metadata };
+        self
+    }
+    pub fn build(self) -> ActivityIdentifier {
+        ActivityIdentifier {
+            module: self.module,
+            activity: self.activity,
+            metadata: self.metadata.build(),
+        }
+    }
+}
+
+/// Builder for `UIServerCommand::UpdateMetadataBulk`
+///
+/// The last call for a key wins: `set` after `remove` on the same key sets it, and vice versa
+#[derive(Debug, Clone)]
+pub struct MetadataUpdateBuilder {
+    activity_id: ActivityIdentifier,
+    set: RHashMap<RString, RString>,
+    remove: Vec<RString>,
+}
+
+impl MetadataUpdateBuilder {
+    pub fn new(activity_id: &ActivityIdentifier) -> Self {
+        Self {
+            activity_id: activity_id.clone(),
+            set: RHashMap::new(),
+            remove: Vec::new(),
+        }
+    }
+    pub fn set(mut self, key: &str, value: &str) -> Self {
+        self.remove.retain(|k| k.as_str() != key);
+        self.set.insert(key.into(), value.into());
+        self
+    }
+    pub fn remove(mut self, key: &str) -> Self {
+        self.set.remove(key);
+        if !self.remove.iter().any(|k| k.as_str() == key) {
+            self.remove.push(key.into());
+        }
+        self
+    }
+    pub fn build(self) -> UIServerCommand {
+        UIServerCommand::UpdateMetadataBulk {
+            activity_id: self.activity_id,
+            set: self.set,
+            remove: self.remove.into(),
+        }
+    }
+}
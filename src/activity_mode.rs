@@ -0,0 +1,92 @@
+use std::fmt::Display;
+
+use abi_stable::StableAbi;
+
+use crate::geometry::SizeAbi;
+
+/// Size mode of an activity widget, from the smallest to the biggest
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ActivityMode {
+    /// Only an icon or a small indicator
+    #[default]
+    Minimal = 0,
+    /// A single line of information
+    Compact = 1,
+    /// The full content of the activity
+    Expanded = 2,
+    /// Shown over the other activities, like a notification
+    Overlay = 3,
+}
+
+impl ActivityMode {
+    pub const ALL: [ActivityMode; 4] = [
+        ActivityMode::Minimal,
+        ActivityMode::Compact,
+        ActivityMode::Expanded,
+        ActivityMode::Overlay,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ActivityMode::Minimal => "minimal",
+            ActivityMode::Compact => "compact",
+            ActivityMode::Expanded => "expanded",
+            ActivityMode::Overlay => "overlay",
+        }
+    }
+}
+
+impl Display for ActivityMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl From<ActivityMode> for u8 {
+    fn from(mode: ActivityMode) -> Self {
+        mode as u8
+    }
+}
+
+/// Step of a [`ModeTransition`]
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TransitionPhase {
+    /// The animation started and will last about `duration_ms`, the widget of `to` should be built now
+    Started { duration_ms: u64 },
+    /// Progress of the animation from 0.0 to 1.0, the LayoutManager may not send it
+    Progress { fraction: f64 },
+    /// The animation ended, the widget of `from` can be freed
+    Completed,
+    /// The transition was interrupted, the activity stays in `from`
+    Cancelled,
+}
+
+/// A change of the mode of an activity animated by the LayoutManager
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModeTransition {
+    pub from: ActivityMode,
+    pub to: ActivityMode,
+    /// Size of the activity at the end of the transition
+    pub target_size: SizeAbi,
+
+    #[sabi(last_prefix_field)]
+    pub phase: TransitionPhase,
+}
+
+/// Convert the raw mode ids used before [`ActivityMode`] was introduced
+impl TryFrom<u8> for ActivityMode {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        ActivityMode::ALL
+            .into_iter()
+            .find(|mode| *mode as u8 == value)
+            .ok_or(value)
+    }
+}
@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use crate::events::ConnectivityState;
+
+/// Jittered exponential backoff for retrying network calls in producers
+///
+/// # Examples
+/// ```ignore
+/// let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(300));
+/// loop {
+///     match fetch_weather().await {
+///         Ok(data) => {
+///             backoff.reset();
+///             /* update the activity */
+///             tokio::time::sleep(refresh_interval).await;
+///         }
+///         Err(_) => tokio::time::sleep(backoff.next_delay()).await,
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    multiplier: f64,
+    jitter: f64,
+    attempt: u32,
+    offline: bool,
+    rng_state: u64,
+}
+
+impl Backoff {
+    /// Create a backoff that starts from `initial` and doubles up to `max`,
+    /// randomly shortening every delay by up to 20%
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or_default();
+        Self {
+            initial,
+            max: max.max(initial),
+            multiplier: 2.0,
+            jitter: 0.2,
+            attempt: 0,
+            offline: false,
+            // xorshift must not start from 0
+            rng_state: seed | 1,
+        }
+    }
+    /// Factor applied to the delay after every failure, values lower than 1.0 are treated as 1.0
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+    /// Maximum fraction of the delay that is randomly removed, clamped between 0.0 and 1.0,
+    /// NaN is treated as the default 0.2
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = if jitter.is_nan() {
+            0.2
+        } else {
+            jitter.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    /// Get the delay to wait before the next attempt, and count a failed attempt.
+    ///
+    /// While the host reports no connectivity the maximum delay is returned,
+    /// see [`Backoff::on_connectivity_changed`]
+    pub fn next_delay(&mut self) -> Duration {
+        let base = if self.offline {
+            self.max
+        } else {
+            let factor = self
+                .multiplier
+                .powi(self.attempt.min(i32::MAX as u32) as i32);
+            // the delay can overflow a Duration after many attempts
+            Duration::try_from_secs_f64(self.initial.as_secs_f64() * factor)
+                .unwrap_or(self.max)
+                .min(self.max)
+        };
+        self.attempt = self.attempt.saturating_add(1);
+        let factor = 1.0 - self.jitter * self.next_random();
+        Duration::try_from_secs_f64(base.as_secs_f64() * factor).unwrap_or(base)
+    }
+    /// Number of failed attempts since the last reset
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+    /// Start again from the initial delay, to be called after a successful attempt
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+    /// Update the backoff with the connectivity reported by the host.
+    ///
+    /// When the connection comes back the backoff is reset so the next attempt happens quickly,
+    /// while offline the delays are kept at the maximum to avoid useless attempts
+    pub fn on_connectivity_changed(&mut self, state: &ConnectivityState) {
+        self.offline = !state.is_online();
+        if !self.offline {
+            self.reset();
+        }
+    }
+    /// Call `f` until it succeeds or `max_attempts` attempts failed,
+    /// sleeping the current thread between attempts, the last error is returned
+    pub fn retry_blocking<T, E>(
+        &mut self,
+        max_attempts: u32,
+        mut f: impl FnMut() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match f() {
+                Ok(value) => {
+                    self.reset();
+                    return Ok(value);
+                }
+                Err(err) => {
+                    if attempts >= max_attempts {
+                        return Err(err);
+                    }
+                    std::thread::sleep(self.next_delay());
+                }
+            }
+        }
+    }
+
+    /// xorshift64, returns a value in `[0, 1)`
+    fn next_random(&mut self) -> f64 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng_state = x;
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delays() {
+        let mut backoff =
+            Backoff::new(Duration::from_millis(100), Duration::from_millis(1000)).with_jitter(0.0);
+        let delays: Vec<_> = (0..6).map(|_| backoff.next_delay().as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(backoff.attempt(), 6);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_millis(100));
+
+        backoff.on_connectivity_changed(&ConnectivityState::Offline);
+        assert_eq!(backoff.next_delay(), Duration::from_millis(1000));
+        backoff.on_connectivity_changed(&ConnectivityState::Online {
+            interface: "wlan0".into(),
+            ssid: abi_stable::std_types::ROption::RNone,
+        });
+        assert_eq!(backoff.attempt(), 0);
+        assert_eq!(backoff.next_delay(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_backoff_jitter() {
+        let mut backoff =
+            Backoff::new(Duration::from_millis(1000), Duration::from_millis(1000)).with_jitter(0.5);
+        for _ in 0..100 {
+            let delay = backoff.next_delay();
+            assert!(delay <= Duration::from_millis(1000));
+            assert!(delay >= Duration::from_millis(500));
+        }
+
+        let mut backoff = Backoff::new(Duration::from_millis(1000), Duration::from_millis(1000))
+            .with_jitter(f64::NAN);
+        for _ in 0..100 {
+            let delay = backoff.next_delay();
+            assert!(delay <= Duration::from_millis(1000));
+            assert!(delay >= Duration::from_millis(800));
+        }
+    }
+
+    #[test]
+    fn test_backoff_overflow() {
+        let mut backoff = Backoff::new(Duration::from_secs(1 << 40), Duration::MAX)
+            .with_multiplier(f64::INFINITY)
+            .with_jitter(0.0);
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1 << 40));
+        assert_eq!(backoff.next_delay(), Duration::MAX);
+        for _ in 0..100 {
+            backoff.next_delay();
+        }
+    }
+
+    #[test]
+    fn test_backoff_retry() {
+        let mut backoff =
+            Backoff::new(Duration::from_millis(1), Duration::from_millis(1)).with_jitter(0.0);
+        let mut calls = 0;
+        let res: Result<(), u32> = backoff.retry_blocking(3, || {
+            calls += 1;
+            Err(calls)
+        });
+        assert_eq!(res, Err(3));
+
+        let mut calls = 0;
+        let res: Result<u32, ()> = backoff.retry_blocking(3, || {
+            calls += 1;
+            if calls == 2 {
+                Ok(calls)
+            } else {
+                Err(())
+            }
+        });
+        assert_eq!(res, Ok(2));
+        assert_eq!(backoff.attempt(), 0);
+    }
+}
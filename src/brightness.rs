@@ -0,0 +1,129 @@
+use abi_stable::{
+    external_types::crossbeam_channel::RSender,
+    std_types::{RBoxError, ROption, RResult, RString, RVec},
+    StableAbi,
+};
+
+/// A backlight or led device managed by the host
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BrightnessDevice {
+    /// Device name, like `intel_backlight`
+    pub name: RString,
+    /// Device class, usually `backlight` or `leds`
+    pub class: RString,
+    /// Current raw brightness
+    pub brightness: u32,
+
+    #[sabi(last_prefix_field)]
+    pub max_brightness: u32,
+}
+
+impl BrightnessDevice {
+    /// Current brightness as a value between 0.0 and 1.0
+    pub fn fraction(&self) -> f64 {
+        if self.max_brightness == 0 {
+            return 0.0;
+        }
+        self.brightness as f64 / self.max_brightness as f64
+    }
+}
+
+/// Brightness value to apply to a device
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BrightnessValue {
+    /// Raw value, clamped by the host to `max_brightness`
+    Absolute(u32),
+    /// Percentage of `max_brightness`, clamped by the host to 100
+    Percent(u8),
+}
+
+impl BrightnessValue {
+    /// Raw brightness to write for a device with `max_brightness`, clamped as described by the variants
+    pub fn to_raw(self, max_brightness: u32) -> u32 {
+        match self {
+            BrightnessValue::Absolute(value) => value.min(max_brightness),
+            BrightnessValue::Percent(percent) => {
+                (max_brightness as u64 * percent.min(100) as u64 / 100) as u32
+            }
+        }
+    }
+}
+
+/// A request to the host brightness service.
+///
+/// The host is responsible for permissions (logind, udev rules or a helper),
+/// so modules never write to `/sys` directly.
+#[repr(C)]
+#[derive(StableAbi)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum BrightnessRequest {
+    /// List all the devices the host can control
+    ListDevices {
+        #[cfg_attr(feature = "serde", serde(skip))]
+        reply: RSender<RResult<RVec<BrightnessDevice>, RBoxError>>,
+    },
+    /// Get the current state of a device
+    GetBrightness {
+        device: RString,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        reply: RSender<RResult<BrightnessDevice, RBoxError>>,
+    },
+    /// Set the brightness of a device, the reply is optional
+    SetBrightness {
+        device: RString,
+        value: BrightnessValue,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        reply: ROption<RSender<RResult<(), RBoxError>>>,
+    },
+    /// Receive the new state of a device (or of all the devices if `device` is `RNone`) every time it changes.
+    ///
+    /// The subscription ends when the receiver is dropped
+    Subscribe {
+        device: ROption<RString>,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        sender: RSender<BrightnessDevice>,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::module::UIServerCommand;
+
+    #[test]
+    fn test_brightness_value() {
+        assert_eq!(BrightnessValue::Absolute(300).to_raw(255), 255);
+        assert_eq!(BrightnessValue::Absolute(100).to_raw(255), 100);
+        assert_eq!(BrightnessValue::Percent(50).to_raw(255), 127);
+        assert_eq!(BrightnessValue::Percent(200).to_raw(255), 255);
+        assert_eq!(BrightnessValue::Percent(100).to_raw(u32::MAX), u32::MAX);
+
+        let mut device = BrightnessDevice {
+            name: "intel_backlight".into(),
+            class: "backlight".into(),
+            brightness: 96000,
+            max_brightness: 120000,
+        };
+        assert_eq!(device.fraction(), 0.8);
+        device.max_brightness = 0;
+        assert_eq!(device.fraction(), 0.0);
+    }
+
+    #[test]
+    fn test_brightness_command() {
+        let (reply, _) = abi_stable::external_types::crossbeam_channel::unbounded();
+        let command = UIServerCommand::Brightness {
+            request: BrightnessRequest::SetBrightness {
+                device: "intel_backlight".into(),
+                value: BrightnessValue::Percent(40),
+                reply: ROption::RSome(reply),
+            },
+        };
+        assert_eq!(command.name(), "Brightness");
+        assert!(command.activity_id().is_none());
+    }
+}
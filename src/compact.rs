@@ -0,0 +1,119 @@
+//! Compact single-line formatting for logging.
+//!
+//! The derived `Debug` of the commands prints every field, including the whole metadata,
+//! which is expensive in hot logging paths. [`Compact`] formats the same values on a single line,
+//! truncates the metadata and doesn't allocate.
+//!
+//! # Examples
+//! ```ignore
+//! log::trace!("received {}", command.compact());
+//! // received AddActivity(player@music{window=main, +2})
+//! ```
+
+use std::fmt::{Display, Formatter, Result, Write};
+
+use abi_stable::std_types::{ROption, Tuple2};
+
+use crate::module::{ActivityIdentifier, UIServerCommand};
+
+/// Maximum number of metadata entries shown
+const MAX_METADATA_ENTRIES: usize = 2;
+/// Maximum number of characters of a metadata value
+const MAX_VALUE_CHARS: usize = 16;
+
+/// Wrapper that formats the inner value in the compact format
+#[derive(Clone, Copy)]
+pub struct Compact<'a, T: ?Sized>(pub &'a T);
+
+pub trait CompactDisplay {
+    fn compact(&self) -> Compact<'_, Self> {
+        Compact(self)
+    }
+}
+
+impl CompactDisplay for ActivityIdentifier {}
+impl CompactDisplay for UIServerCommand {}
+
+impl Display for Compact<'_, ActivityIdentifier> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let id = self.0;
+        write!(f, "{id}")?;
+        let metadata = &id.metadata;
+        let len = metadata.additional_metadata.len();
+        if metadata.window_name.is_none() && len == 0 {
+            return Ok(());
+        }
+        f.write_char('{')?;
+        let mut first = true;
+        if let ROption::RSome(window) = &metadata.window_name {
+            write!(f, "window={}", Truncated(window))?;
+            first = false;
+        }
+        for Tuple2(key, value) in metadata
+            .additional_metadata
+            .iter()
+            .take(MAX_METADATA_ENTRIES)
+        {
+            if !first {
+                f.write_str(", ")?;
+            }
+            write!(f, "{key}={}", Truncated(value))?;
+            first = false;
+        }
+        if len > MAX_METADATA_ENTRIES {
+            write!(f, ", +{}", len - MAX_METADATA_ENTRIES)?;
+        }
+        f.write_char('}')
+    }
+}
+
+impl Display for Compact<'_, UIServerCommand> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let command = self.0;
+        f.write_str(command.name())?;
+        if let UIServerCommand::Batch(commands) = command {
+            write!(f, "[{}]", commands.len())?;
+        }
+        if let Some(id) = command.activity_id() {
+            write!(f, "({})", id.compact())?;
+        }
+        Ok(())
+    }
+}
+
+struct Truncated<'a>(&'a str);
+
+impl Display for Truncated<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.0.char_indices().nth(MAX_VALUE_CHARS) {
+            Some((end, _)) => write!(f, "{}…", &self.0[..end]),
+            None => f.write_str(self.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compact_identifier() {
+        let mut id = ActivityIdentifier::new("music", "player");
+        assert_eq!(id.compact().to_string(), "player@music");
+        id.metadata_mut().set_window_name("main");
+        id.metadata_mut()
+            .set_additional_metadata("title".into(), "a very long song title".into());
+        assert_eq!(
+            id.compact().to_string(),
+            "player@music{window=main, title=a very long song…}"
+        );
+        let command = UIServerCommand::RemoveActivity {
+            activity_id: id,
+            reply: ROption::RNone,
+        };
+        assert!(command
+            .compact()
+            .to_string()
+            .starts_with("RemoveActivity(player@music{"));
+    }
+}
@@ -0,0 +1,364 @@
+//! Compatibility with modules built for an older version of the abi.
+//!
+//! The interface of version 0.2 of the abi ([`SabiModule`](crate::module::SabiModule) and [`ModuleBuilder`](crate::module::ModuleBuilder))
+//! is frozen in [`v1`], with the same type names and layout, so the host can keep loading the 0.2 modules
+//! after the next incompatible change: if loading a library as a [`ModuleBuilderRef`](crate::module::ModuleBuilderRef) fails,
+//! the host can load it with [`v1::ModuleBuilderRef::load_v1_from_file`] and create the module with [`v1::ModuleBuilderRef::new_compat`].
+//!
+//! The adapted module uses the default implementation for all the methods that were added after v1.
+//! The commands and the other types used by the v1 interface are shared with the current one,
+//! when one of them changes its 0.2 shape must be frozen in [`v1`] as well.
+//!
+//! # Examples
+//! ```ignore
+//! let module = match ModuleBuilderRef::load_from_file(&path) {
+//!     Ok(builder) => (builder.new())(app_send),
+//!     Err(_) => unsafe { v1::ModuleBuilderRef::load_v1_from_file(&path)? }.new_compat(app_send),
+//! };
+//! ```
+
+use std::mem::ManuallyDrop;
+
+use abi_stable::{
+    external_types::crossbeam_channel::RSender,
+    sabi_trait::TD_Opaque,
+    std_types::{RBoxError, ROption, RResult, RString, RVec},
+};
+
+use crate::{
+    config::ConfigError,
+    events::{
+        AppEvent, CompositorEvent, ConnectivityState, InteractionEvent, KeyEvent, ModuleServerEvent,
+    },
+    feedback::CommandError,
+    history::CommandHistory,
+    logger::SabiLogger,
+    module::{ActivityIdentifier, ModuleType, SabiModule, SabiModule_TO},
+};
+
+/// The module interface as it was in version 0.2 of the abi
+pub mod v1 {
+    use std::path::Path;
+
+    use abi_stable::{
+        declare_root_module_statics,
+        external_types::crossbeam_channel::RSender,
+        library::{lib_header_from_path, LibraryError, RootModule},
+        sabi_trait,
+        sabi_types::VersionStrings,
+        std_types::{RBox, RBoxError, ROption, RResult, RSlice, RStr, RString, RVec},
+        StableAbi,
+    };
+
+    use super::ModuleV1CompatAdapter;
+    use crate::{
+        config::ConfigError,
+        events::{
+            AppEvent, CompositorEvent, ConnectivityState, InteractionEvent, KeyEvent,
+            ModuleServerEvent,
+        },
+        feedback::CommandError,
+        history::CommandHistory,
+        host::HostContextRef,
+        logger::SabiLogger,
+        module::{
+            ActivityDeclaration, ActivityIdentifier, ModuleFlags, ModuleIcon, UIServerCommand,
+        },
+        scheduling::SchedulingHint,
+        NotImplementedError,
+    };
+
+    pub type ModuleType = SabiModule_TO<'static, RBox<()>>;
+
+    /// `SabiModule` of version 0.2, the methods and their order must not change
+    #[sabi_trait]
+    pub trait SabiModule {
+        fn init(&self);
+
+        fn update_config(&mut self, config: RString) -> RResult<(), ConfigError>;
+
+        fn restart_producers(&self);
+
+        fn default_config(&self) -> RResult<RString, RBoxError> {
+            RResult::RErr(RBoxError::new(NotImplementedError::default()))
+        }
+
+        #[sabi(last_prefix_field)]
+        fn cli_command(&self, _command: RString) -> RResult<RString, RBoxError> {
+            RResult::RErr(RBoxError::new(NotImplementedError::default()))
+        }
+
+        fn on_connectivity_changed(&self, _state: ConnectivityState) {}
+
+        fn validate_config(&self, _config: RString) -> RResult<(), ConfigError> {
+            RResult::ROk(())
+        }
+
+        fn on_compositor_event(&self, _event: CompositorEvent) {}
+
+        fn on_activity_interaction(&self, _id: ActivityIdentifier, _action: InteractionEvent) {}
+
+        fn set_logger(&self, logger: SabiLogger) {
+            let _ = crate::logger::init_logger(logger);
+        }
+
+        fn restore_state(&mut self, _history: CommandHistory) {}
+
+        fn on_app_event(&self, _event: AppEvent) {}
+
+        fn export_effective_config(&self) -> RResult<RString, RBoxError> {
+            RResult::RErr(RBoxError::new(NotImplementedError::default()))
+        }
+
+        fn on_key_event(&self, _id: ActivityIdentifier, _event: KeyEvent) -> bool {
+            false
+        }
+
+        fn on_module_message(&self, _payload: RVec<u8>) -> ROption<RVec<u8>> {
+            ROption::RNone
+        }
+
+        fn on_timer(&self, _token: u64) {}
+
+        fn set_error_sender(&self, _sender: RSender<CommandError>) {}
+
+        fn shutdown(&mut self) {}
+
+        /// The modules implement it, the default is never called by the adapter
+        fn handle_event(&self, _event: ModuleServerEvent) -> bool {
+            false
+        }
+    }
+
+    /// `ModuleBuilder` of version 0.2, the fields and their order must not change
+    #[repr(C)]
+    #[derive(StableAbi)]
+    #[sabi(kind(Prefix(prefix_ref = ModuleBuilderRef)))]
+    #[sabi(missing_field(panic))]
+    pub struct ModuleBuilder {
+        pub new:
+            extern "C" fn(app_send: RSender<UIServerCommand>) -> RResult<ModuleType, RBoxError>,
+
+        #[sabi(last_prefix_field)]
+        pub name: RStr<'static>,
+
+        #[sabi(missing_field(default))]
+        pub version: RStr<'static>,
+        #[sabi(missing_field(default))]
+        pub min_dynisland_version: RStr<'static>,
+        #[sabi(missing_field(default))]
+        pub description: RStr<'static>,
+        #[sabi(missing_field(default))]
+        pub author: RStr<'static>,
+        #[sabi(missing_field(default))]
+        pub license: RStr<'static>,
+        #[sabi(missing_field(default))]
+        pub icon: ROption<ModuleIcon>,
+        #[sabi(missing_field(default))]
+        pub flags: ModuleFlags,
+        #[sabi(missing_field(default))]
+        pub new_with_config: ROption<
+            extern "C" fn(
+                app_send: RSender<UIServerCommand>,
+                config: RString,
+            ) -> RResult<ModuleType, RBoxError>,
+        >,
+        #[sabi(missing_field(default))]
+        pub new_with_context: ROption<
+            extern "C" fn(
+                app_send: RSender<UIServerCommand>,
+                ctx: HostContextRef,
+            ) -> RResult<ModuleType, RBoxError>,
+        >,
+        #[sabi(missing_field(default))]
+        pub scheduling: SchedulingHint,
+        #[sabi(missing_field(default))]
+        pub activities: RSlice<'static, ActivityDeclaration>,
+        #[sabi(missing_field(default))]
+        pub config_key: ROption<RStr<'static>>,
+        #[sabi(missing_field(default))]
+        pub negotiate: ROption<extern "C" fn(host_features: RVec<RString>) -> RVec<RString>>,
+        #[sabi(missing_field(default))]
+        pub priority: i32,
+        #[sabi(missing_field(default))]
+        pub drop_module: ROption<extern "C" fn(module: ModuleType)>,
+        #[sabi(missing_field(default))]
+        pub checksum: ROption<RStr<'static>>,
+        #[sabi(missing_field(default))]
+        pub signature: ROption<RSlice<'static, u8>>,
+        #[sabi(missing_field(default))]
+        pub default_config: ROption<extern "C" fn() -> RResult<RString, RBoxError>>,
+    }
+
+    impl RootModule for ModuleBuilderRef {
+        declare_root_module_statics! {ModuleBuilderRef}
+        const BASE_NAME: &'static str = "module";
+        const NAME: &'static str = "module";
+        const VERSION_STRINGS: VersionStrings = VersionStrings::new("0.2.0");
+    }
+
+    impl ModuleBuilderRef {
+        /// Load a library built against any 0.2 version of the abi, checking only its version strings.
+        ///
+        /// The layout of every type records the version of this crate, so after an incompatible change
+        /// the layout check of [`RootModule::load_from_file`] always fails for the 0.2 libraries.
+        ///
+        /// # Safety
+        /// The library must export a 0.2 `ModuleBuilder`, and the types shared with the current
+        /// interface must still have their 0.2 layout
+        pub unsafe fn load_v1_from_file(path: &Path) -> Result<Self, LibraryError> {
+            let header = lib_header_from_path(path)?;
+            unsafe { header.init_root_module_with_unchecked_layout::<Self>() }
+        }
+
+        /// Create the module with `new` and wrap it in a [`ModuleV1CompatAdapter`]
+        pub fn new_compat(
+            &self,
+            app_send: RSender<UIServerCommand>,
+        ) -> RResult<crate::module::ModuleType, RBoxError> {
+            let drop_module = self.drop_module();
+            (self.new())(app_send)
+                .map(|module| ModuleV1CompatAdapter::new(module, drop_module).into_module())
+        }
+    }
+}
+
+/// Implementation of the current [`SabiModule`] over a [`v1`] module
+///
+/// When the adapter is dropped the module is destroyed with the `drop_module` of its builder, if it has one
+pub struct ModuleV1CompatAdapter {
+    inner: ManuallyDrop<v1::ModuleType>,
+    drop_module: ROption<extern "C" fn(module: v1::ModuleType)>,
+}
+
+impl ModuleV1CompatAdapter {
+    pub fn new(
+        inner: v1::ModuleType,
+        drop_module: ROption<extern "C" fn(module: v1::ModuleType)>,
+    ) -> Self {
+        Self {
+            inner: ManuallyDrop::new(inner),
+            drop_module,
+        }
+    }
+    pub fn into_module(self) -> ModuleType {
+        SabiModule_TO::from_value(self, TD_Opaque)
+    }
+    pub fn inner(&self) -> &v1::ModuleType {
+        &self.inner
+    }
+}
+
+impl Drop for ModuleV1CompatAdapter {
+    fn drop(&mut self) {
+        // SAFETY: inner is not used after this
+        let inner = unsafe { ManuallyDrop::take(&mut self.inner) };
+        match self.drop_module {
+            ROption::RSome(drop_module) => drop_module(inner),
+            ROption::RNone => drop(inner),
+        }
+    }
+}
+
+impl SabiModule for ModuleV1CompatAdapter {
+    fn init(&self) {
+        self.inner.init()
+    }
+    fn update_config(&mut self, config: RString) -> RResult<(), ConfigError> {
+        self.inner.update_config(config)
+    }
+    fn restart_producers(&self) {
+        self.inner.restart_producers()
+    }
+    fn default_config(&self) -> RResult<RString, RBoxError> {
+        self.inner.default_config()
+    }
+    fn cli_command(&self, command: RString) -> RResult<RString, RBoxError> {
+        self.inner.cli_command(command)
+    }
+    fn on_connectivity_changed(&self, state: ConnectivityState) {
+        self.inner.on_connectivity_changed(state)
+    }
+    fn validate_config(&self, config: RString) -> RResult<(), ConfigError> {
+        self.inner.validate_config(config)
+    }
+    fn on_compositor_event(&self, event: CompositorEvent) {
+        self.inner.on_compositor_event(event)
+    }
+    fn on_activity_interaction(&self, id: ActivityIdentifier, action: InteractionEvent) {
+        self.inner.on_activity_interaction(id, action)
+    }
+    fn set_logger(&self, logger: SabiLogger) {
+        self.inner.set_logger(logger)
+    }
+    fn restore_state(&mut self, history: CommandHistory) {
+        self.inner.restore_state(history)
+    }
+    fn on_app_event(&self, event: AppEvent) {
+        self.inner.on_app_event(event)
+    }
+    fn export_effective_config(&self) -> RResult<RString, RBoxError> {
+        self.inner.export_effective_config()
+    }
+    fn on_key_event(&self, id: ActivityIdentifier, event: KeyEvent) -> bool {
+        self.inner.on_key_event(id, event)
+    }
+    fn on_module_message(&self, payload: RVec<u8>) -> ROption<RVec<u8>> {
+        self.inner.on_module_message(payload)
+    }
+    fn on_timer(&self, token: u64) {
+        self.inner.on_timer(token)
+    }
+    fn set_error_sender(&self, sender: RSender<CommandError>) {
+        self.inner.set_error_sender(sender)
+    }
+    fn shutdown(&mut self) {
+        self.inner.shutdown()
+    }
+    fn handle_event(&self, event: ModuleServerEvent) -> bool {
+        self.inner.handle_event(event)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use abi_stable::{abi_stability::abi_checking::check_layout_compatibility, StableAbi};
+
+    use super::*;
+
+    #[test]
+    fn test_v1_layout() {
+        // a library built against the current version must load as v1
+        let res = check_layout_compatibility(
+            <v1::ModuleBuilderRef as StableAbi>::LAYOUT,
+            <crate::module::ModuleBuilderRef as StableAbi>::LAYOUT,
+        );
+        assert!(res.is_ok(), "{:#?}", res.unwrap_err());
+    }
+
+    #[test]
+    fn test_adapter_forwards() {
+        struct TestModule;
+        impl v1::SabiModule for TestModule {
+            fn init(&self) {}
+            fn update_config(&mut self, _config: RString) -> RResult<(), ConfigError> {
+                RResult::RErr(ConfigError::new("invalid"))
+            }
+            fn restart_producers(&self) {}
+            fn on_timer(&self, token: u64) {
+                assert_eq!(token, 7);
+            }
+            fn handle_event(&self, _event: ModuleServerEvent) -> bool {
+                true
+            }
+        }
+        let inner = v1::SabiModule_TO::from_value(TestModule, TD_Opaque);
+        let mut module = ModuleV1CompatAdapter::new(inner, ROption::RNone).into_module();
+        assert_eq!(
+            module.update_config("".into()).unwrap_err(),
+            ConfigError::new("invalid")
+        );
+        module.on_timer(7);
+        assert!(module.handle_event(ModuleServerEvent::Timer { token: 7 }));
+    }
+}
@@ -0,0 +1,206 @@
+//! Boolean conditions over the metadata of an activity, like "show only when the battery is below 30%".
+//!
+//! Modules publish a [`Condition`] in the `ActivityMetadata` of an activity,
+//! the LayoutManager evaluates it again every time the metadata changes,
+//! [`Condition::keys`] returns the keys it needs to watch.
+
+use std::fmt::Display;
+
+use abi_stable::{
+    std_types::{RString, RVec},
+    StableAbi,
+};
+
+use crate::module::ActivityMetadata;
+
+/// Comparison operator of [`Condition::Compare`]
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// The value of the key contains the string
+    Contains,
+}
+
+/// A boolean expression over metadata keys
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Condition {
+    /// Always true
+    Always,
+    /// True if the key is set
+    Exists { key: RString },
+    /// Compare the value of a key with `value`.
+    ///
+    /// If both can be parsed as numbers they are compared as numbers, otherwise as strings.
+    /// It's false if the key is not set
+    Compare {
+        key: RString,
+        op: CompareOp,
+        value: RString,
+    },
+    /// True if all the conditions are true
+    All(RVec<Condition>),
+    /// True if at least one of the conditions is true
+    Any(RVec<Condition>),
+    /// True if none of the conditions is true
+    Not(RVec<Condition>),
+}
+
+impl Condition {
+    pub fn exists(key: &str) -> Self {
+        Condition::Exists { key: key.into() }
+    }
+    pub fn compare(key: &str, op: CompareOp, value: impl Display) -> Self {
+        Condition::Compare {
+            key: key.into(),
+            op,
+            value: value.to_string().into(),
+        }
+    }
+    pub fn and(self, other: Condition) -> Self {
+        match self {
+            Condition::All(mut conditions) => {
+                conditions.push(other);
+                Condition::All(conditions)
+            }
+            this => Condition::All(vec![this, other].into()),
+        }
+    }
+    pub fn or(self, other: Condition) -> Self {
+        match self {
+            Condition::Any(mut conditions) => {
+                conditions.push(other);
+                Condition::Any(conditions)
+            }
+            this => Condition::Any(vec![this, other].into()),
+        }
+    }
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Condition::Not(vec![self].into())
+    }
+
+    /// Evaluate the condition, `lookup` returns the value of a key
+    pub fn evaluate(&self, lookup: &impl Fn(&str) -> Option<String>) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::Exists { key } => lookup(key).is_some(),
+            Condition::Compare { key, op, value } => match lookup(key) {
+                Some(current) => compare(&current, *op, value),
+                None => false,
+            },
+            Condition::All(conditions) => conditions.iter().all(|c| c.evaluate(lookup)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.evaluate(lookup)),
+            Condition::Not(conditions) => !conditions.iter().any(|c| c.evaluate(lookup)),
+        }
+    }
+    /// Evaluate the condition on the additional metadata of an activity
+    ///
+    /// Keys missing from the string entries are looked up in the typed values, converted to strings
+    pub fn evaluate_metadata(&self, metadata: &ActivityMetadata) -> bool {
+        self.evaluate(&|key| {
+            metadata
+                .additional_metadata(key)
+                .or_else(|| metadata.value(key).map(|value| value.to_string()))
+        })
+    }
+
+    /// Keys used by the condition, without duplicates
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys = Vec::new();
+        self.collect_keys(&mut keys);
+        keys
+    }
+    fn collect_keys<'a>(&'a self, keys: &mut Vec<&'a str>) {
+        match self {
+            Condition::Always => {}
+            Condition::Exists { key } | Condition::Compare { key, .. } => {
+                if !keys.contains(&key.as_str()) {
+                    keys.push(key);
+                }
+            }
+            Condition::All(conditions)
+            | Condition::Any(conditions)
+            | Condition::Not(conditions) => conditions.iter().for_each(|c| c.collect_keys(keys)),
+        }
+    }
+}
+
+fn compare(current: &str, op: CompareOp, value: &str) -> bool {
+    use std::cmp::Ordering;
+    if op == CompareOp::Contains {
+        return current.contains(value);
+    }
+    let ordering = match (current.trim().parse::<f64>(), value.trim().parse::<f64>()) {
+        (Ok(current), Ok(value)) => match current.partial_cmp(&value) {
+            Some(ordering) => ordering,
+            None => return op == CompareOp::Ne,
+        },
+        _ => current.cmp(value),
+    };
+    match op {
+        CompareOp::Eq => ordering == Ordering::Equal,
+        CompareOp::Ne => ordering != Ordering::Equal,
+        CompareOp::Lt => ordering == Ordering::Less,
+        CompareOp::Le => ordering != Ordering::Greater,
+        CompareOp::Gt => ordering == Ordering::Greater,
+        CompareOp::Ge => ordering != Ordering::Less,
+        CompareOp::Contains => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_condition_evaluate() {
+        let mut metadata = ActivityMetadata::new();
+        metadata.set_additional_metadata("battery.level".into(), "25".into());
+        metadata.set_additional_metadata("battery.state".into(), "discharging".into());
+
+        let low = Condition::compare("battery.level", CompareOp::Lt, 30).and(Condition::compare(
+            "battery.state",
+            CompareOp::Eq,
+            "discharging",
+        ));
+        assert!(low.evaluate_metadata(&metadata));
+        assert!(!low.clone().not().evaluate_metadata(&metadata));
+        // numeric, not lexicographic
+        assert!(Condition::compare("battery.level", CompareOp::Gt, 3).evaluate_metadata(&metadata));
+        assert!(!Condition::compare("missing", CompareOp::Ne, 1).evaluate_metadata(&metadata));
+        assert!(Condition::exists("missing")
+            .or(Condition::compare(
+                "battery.state",
+                CompareOp::Contains,
+                "charg"
+            ))
+            .evaluate_metadata(&metadata));
+        assert_eq!(low.keys(), ["battery.level", "battery.state"]);
+    }
+
+    #[test]
+    fn test_condition_typed_metadata() {
+        let mut metadata = ActivityMetadata::new();
+        metadata.set_exclusive(true);
+        metadata.set_order_weight(5);
+        assert!(metadata.is_visible());
+
+        metadata.set_visibility_condition(
+            Condition::compare(crate::metadata::KEY_EXCLUSIVE, CompareOp::Eq, "true").and(
+                Condition::compare(crate::metadata::KEY_ORDER_WEIGHT, CompareOp::Gt, 3),
+            ),
+        );
+        assert!(metadata.is_visible());
+        metadata.set_exclusive(false);
+        assert!(!metadata.is_visible());
+    }
+}
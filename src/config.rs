@@ -0,0 +1,358 @@
+use std::fmt::Display;
+
+use abi_stable::{
+    std_types::{RBoxError, ROption, RResult, RString, RVec},
+    StableAbi,
+};
+
+use crate::{
+    module::{ModuleBuilderRef, ModuleType},
+    panic::PanicError,
+};
+
+/// Error produced while parsing or validating the config section of a module
+///
+/// The host uses the location to print a precise diagnostic, like
+/// `error at music.scrolling_speed (line 12, column 5): expected a float`
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Human readable description of the error
+    pub message: RString,
+    /// Line of the config file, starting from 1
+    pub line: ROption<u32>,
+    /// Column of the config file, starting from 1
+    pub column: ROption<u32>,
+
+    /// Path of the key that caused the error, from the outermost section,
+    /// for example `["music", "scrolling_speed"]`
+    #[sabi(last_prefix_field)]
+    pub key_path: RVec<RString>,
+}
+
+impl ConfigError {
+    pub fn new(message: &str) -> Self {
+        Self {
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+    /// Set the location of the error in the config file
+    pub fn at(mut self, line: u32, column: u32) -> Self {
+        self.line = ROption::RSome(line);
+        self.column = ROption::RSome(column);
+        self
+    }
+    /// Append a key to the end of the key path
+    pub fn key(mut self, key: &str) -> Self {
+        self.key_path.push(key.into());
+        self
+    }
+    /// Prepend a section to the key path, used by the host to add the module name
+    pub fn in_section(mut self, section: &str) -> Self {
+        self.key_path.insert(0, section.into());
+        self
+    }
+    /// Key path joined with `.`
+    pub fn key_path_string(&self) -> String {
+        self.key_path
+            .iter()
+            .map(|key| key.as_str())
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error")?;
+        if !self.key_path.is_empty() {
+            write!(f, " at {}", self.key_path_string())?;
+        }
+        match (self.line, self.column) {
+            (ROption::RSome(line), ROption::RSome(column)) => {
+                write!(f, " (line {line}, column {column})")?
+            }
+            (ROption::RSome(line), ROption::RNone) => write!(f, " (line {line})")?,
+            _ => {}
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+impl std::error::Error for ConfigError {}
+
+impl From<PanicError> for ConfigError {
+    fn from(err: PanicError) -> Self {
+        ConfigError::new(&err.to_string())
+    }
+}
+
+/// Effective config of a single module, see [`ConfigSnapshot`]
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleConfigSnapshot {
+    /// Name of the config section of the module, see `ModuleBuilderRef::config_section`
+    pub config_key: RString,
+    /// Version of the module that exported the config, empty if unknown
+    pub module_version: RString,
+
+    /// Config returned by `SabiModule::export_effective_config`, in ron format
+    #[sabi(last_prefix_field)]
+    pub config: RString,
+}
+
+impl ModuleConfigSnapshot {
+    /// Export the effective config of `module`, created from `builder`
+    pub fn export(builder: ModuleBuilderRef, module: &ModuleType) -> RResult<Self, RBoxError> {
+        module.export_effective_config().map(|config| Self {
+            config_key: builder.config_section().into(),
+            module_version: builder.version().into(),
+            config,
+        })
+    }
+}
+
+/// Effective config of every module, used by `dynisland config export` and `dynisland config import`.
+///
+/// The modules are kept sorted by config key so the same configs always produce the same file.
+/// To import it the host passes every config to `validate_config` and `update_config` of the module with the same config key
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigSnapshot {
+    /// Version of the host that exported the configs
+    pub host_version: RString,
+
+    #[sabi(last_prefix_field)]
+    pub modules: RVec<ModuleConfigSnapshot>,
+}
+
+impl ConfigSnapshot {
+    pub fn new(host_version: &str) -> Self {
+        Self {
+            host_version: host_version.into(),
+            modules: RVec::new(),
+        }
+    }
+    /// Add the config of a module, replacing the one with the same config key
+    pub fn insert(&mut self, module: ModuleConfigSnapshot) {
+        match self
+            .modules
+            .binary_search_by(|other| other.config_key.cmp(&module.config_key))
+        {
+            Ok(idx) => self.modules[idx] = module,
+            Err(idx) => self.modules.insert(idx, module),
+        }
+    }
+    pub fn get(&self, config_key: &str) -> Option<&ModuleConfigSnapshot> {
+        self.modules
+            .iter()
+            .find(|module| module.config_key.as_str() == config_key)
+    }
+}
+
+/// Environment variables that can be used in config values by default
+pub const DEFAULT_INTERPOLATION_VARS: &[&str] = &[
+    "HOME",
+    "USER",
+    "XDG_CONFIG_HOME",
+    "XDG_DATA_HOME",
+    "XDG_CACHE_HOME",
+    "XDG_STATE_HOME",
+    "XDG_RUNTIME_DIR",
+];
+
+/// Error returned when interpolating environment variables in a config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterpolationError {
+    /// The variable is not in the allowlist
+    UnknownVariable { name: String, offset: usize },
+    /// The variable is allowed but not set
+    UnsetVariable { name: String, offset: usize },
+    /// `${` without the closing `}`
+    Unterminated { offset: usize },
+}
+
+impl InterpolationError {
+    /// Byte offset of the `${` that caused the error
+    pub fn offset(&self) -> usize {
+        match self {
+            InterpolationError::UnknownVariable { offset, .. }
+            | InterpolationError::UnsetVariable { offset, .. }
+            | InterpolationError::Unterminated { offset } => *offset,
+        }
+    }
+    /// Convert to a [`ConfigError`] with the line and column of the error in `input`
+    pub fn to_config_error(&self, input: &str) -> ConfigError {
+        let before = &input[..self.offset().min(input.len())];
+        let line = before.matches('\n').count() as u32 + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .map(|line| line.chars().count())
+            .unwrap_or_default() as u32
+            + 1;
+        ConfigError::new(&self.to_string()).at(line, column)
+    }
+}
+
+impl Display for InterpolationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterpolationError::UnknownVariable { name, .. } => {
+                write!(f, "variable ${{{name}}} can't be used in the config")
+            }
+            InterpolationError::UnsetVariable { name, .. } => {
+                write!(f, "variable ${{{name}}} is not set")
+            }
+            InterpolationError::Unterminated { .. } => write!(f, "missing `}}` after `${{`"),
+        }
+    }
+}
+impl std::error::Error for InterpolationError {}
+
+/// Replace `${VAR}` with the value of the environment variable `VAR`,
+/// only the variables in [`DEFAULT_INTERPOLATION_VARS`] are allowed.
+///
+/// `$${` is replaced by a literal `${`, a `$` not followed by `{` is left unchanged.
+/// The `XDG_*` variables fall back to their default value from the XDG base directory spec when not set.
+///
+/// The host applies this to the config before passing it to the modules,
+/// modules can use it to handle configs they read by themselves in the same way
+pub fn interpolate_env(input: &str) -> Result<String, InterpolationError> {
+    interpolate_with(input, DEFAULT_INTERPOLATION_VARS, env_lookup)
+}
+
+/// Like [`interpolate_env`], with a custom allowlist and function to get the value of the variables
+pub fn interpolate_with(
+    input: &str,
+    allowed: &[&str],
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String, InterpolationError> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        let offset = input.len() - rest.len() + start;
+        let after = &rest[start + 1..];
+        if let Some(escaped) = after.strip_prefix("${") {
+            output.push_str("${");
+            rest = escaped;
+        } else if let Some(var) = after.strip_prefix('{') {
+            let end = var
+                .find('}')
+                .ok_or(InterpolationError::Unterminated { offset })?;
+            let name = &var[..end];
+            if !allowed.contains(&name) {
+                return Err(InterpolationError::UnknownVariable {
+                    name: name.to_string(),
+                    offset,
+                });
+            }
+            let value = lookup(name).ok_or_else(|| InterpolationError::UnsetVariable {
+                name: name.to_string(),
+                offset,
+            })?;
+            output.push_str(&value);
+            rest = &var[end + 1..];
+        } else {
+            output.push('$');
+            rest = after;
+        }
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn env_lookup(name: &str) -> Option<String> {
+    if let Some(value) = std::env::var(name).ok().filter(|value| !value.is_empty()) {
+        return Some(value);
+    }
+    let home = || std::env::var("HOME").ok();
+    match name {
+        "XDG_CONFIG_HOME" => home().map(|home| format!("{home}/.config")),
+        "XDG_DATA_HOME" => home().map(|home| format!("{home}/.local/share")),
+        "XDG_CACHE_HOME" => home().map(|home| format!("{home}/.cache")),
+        "XDG_STATE_HOME" => home().map(|home| format!("{home}/.local/state")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_snapshot_insert() {
+        let mut snapshot = ConfigSnapshot::new("0.4.0");
+        for (key, config) in [("music", "(a: 1)"), ("clock", "()"), ("music", "(a: 2)")] {
+            snapshot.insert(ModuleConfigSnapshot {
+                config_key: key.into(),
+                config: config.into(),
+                ..Default::default()
+            });
+        }
+        let keys: Vec<_> = snapshot
+            .modules
+            .iter()
+            .map(|m| m.config_key.as_str())
+            .collect();
+        assert_eq!(keys, ["clock", "music"]);
+        assert_eq!(snapshot.get("music").unwrap().config, "(a: 2)");
+        assert!(snapshot.get("battery").is_none());
+    }
+
+    #[test]
+    fn test_config_error_display() {
+        let err = ConfigError::new("expected a float")
+            .key("scrolling_speed")
+            .in_section("music");
+        assert_eq!(
+            err.to_string(),
+            "error at music.scrolling_speed: expected a float"
+        );
+        let err = err.at(12, 5);
+        assert_eq!(
+            err.to_string(),
+            "error at music.scrolling_speed (line 12, column 5): expected a float"
+        );
+        assert_eq!(ConfigError::new("eof").to_string(), "error: eof");
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let lookup = |name: &str| match name {
+            "HOME" => Some("/home/user".to_string()),
+            _ => None,
+        };
+        let allowed = &["HOME", "XDG_RUNTIME_DIR"];
+        assert_eq!(
+            interpolate_with("${HOME}/music $5 $${HOME}", allowed, lookup),
+            Ok("/home/user/music $5 ${HOME}".to_string())
+        );
+        assert_eq!(
+            interpolate_with("a: 1,\nb: \"${PATH}\"", allowed, lookup),
+            Err(InterpolationError::UnknownVariable {
+                name: "PATH".to_string(),
+                offset: 10
+            })
+        );
+        let err =
+            interpolate_with("a: 1,\nb: \"${XDG_RUNTIME_DIR}\"", allowed, lookup).unwrap_err();
+        assert_eq!(
+            err,
+            InterpolationError::UnsetVariable {
+                name: "XDG_RUNTIME_DIR".to_string(),
+                offset: 10
+            }
+        );
+        let config_error = err.to_config_error("a: 1,\nb: \"${XDG_RUNTIME_DIR}\"");
+        assert_eq!(config_error.line, ROption::RSome(2));
+        assert_eq!(config_error.column, ROption::RSome(5));
+        assert_eq!(
+            interpolate_with("${HOME", allowed, lookup),
+            Err(InterpolationError::Unterminated { offset: 0 })
+        );
+    }
+}
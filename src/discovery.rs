@@ -0,0 +1,185 @@
+//! Locate and load module libraries.
+//!
+//! The directories are searched in this order:
+//! - every directory in `DYNISLAND_MODULE_PATH` (separated by `:`)
+//! - `$XDG_DATA_HOME/dynisland/modules` (`~/.local/share/dynisland/modules` if unset)
+//! - `dynisland/modules` in every directory of `$XDG_DATA_DIRS` (`/usr/local/share:/usr/share` if unset)
+//!
+//! The library of the module `music-module` can be named `libmusic_module.so`,
+//! or have a version suffix like `libmusic_module.so.0.2.1` or `libmusic_module-0.2.1.so`.
+//! In the first directory that contains it, the name without a version wins, then the highest version.
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use abi_stable::library::{lib_header_from_path, LibraryError};
+
+use crate::{module::ModuleBuilderRef, version::Version};
+
+/// Environment variable with additional module directories, searched first
+pub const MODULE_PATH_VAR: &str = "DYNISLAND_MODULE_PATH";
+
+/// Error returned while looking for or loading a module library
+#[derive(Debug)]
+pub enum DiscoveryError {
+    /// No library for the module in any of the `searched` directories
+    NotFound {
+        name: String,
+        searched: Vec<PathBuf>,
+    },
+    /// The library exists but it couldn't be loaded, or it's not a compatible module
+    Load { path: PathBuf, error: LibraryError },
+}
+
+impl Display for DiscoveryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiscoveryError::NotFound { name, searched } => {
+                write!(f, "module {name} not found, searched in: ")?;
+                let searched: Vec<_> = searched.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "{}", searched.join(", "))
+            }
+            DiscoveryError::Load { path, error } => {
+                write!(f, "failed to load {}: {error}", path.display())
+            }
+        }
+    }
+}
+impl std::error::Error for DiscoveryError {}
+
+/// Directories that can contain module libraries, in search order
+pub fn module_search_paths() -> Vec<PathBuf> {
+    let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+    let mut paths: Vec<PathBuf> = var(MODULE_PATH_VAR)
+        .map(|value| std::env::split_paths(&value).collect())
+        .unwrap_or_default();
+    let data_home = var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|home| Path::new(&home).join(".local/share")));
+    if let Some(data_home) = data_home {
+        paths.push(data_home.join("dynisland/modules"));
+    }
+    let data_dirs = var("XDG_DATA_DIRS").unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    paths.extend(std::env::split_paths(&data_dirs).map(|dir| dir.join("dynisland/modules")));
+    paths
+}
+
+/// All the module libraries in `dirs`, missing or unreadable directories are skipped
+pub fn find_module_libraries(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut libraries = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut found: Vec<_> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && parse_library_name(path).is_some())
+            .collect();
+        found.sort();
+        libraries.extend(found);
+    }
+    libraries
+}
+
+/// Path of the library of the module `name` in `dirs`
+pub fn find_module(name: &str, dirs: &[PathBuf]) -> Result<PathBuf, DiscoveryError> {
+    let crate_name = name.replace('-', "_");
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let best = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .filter_map(|path| match parse_library_name(&path) {
+                Some((lib_name, version)) if lib_name == crate_name => Some((version, path)),
+                _ => None,
+            })
+            // the library without a version wins, then the highest version
+            .max_by_key(|(version, _)| (version.is_none(), *version));
+        if let Some((_, path)) = best {
+            return Ok(path);
+        }
+    }
+    Err(DiscoveryError::NotFound {
+        name: name.to_string(),
+        searched: dirs.to_vec(),
+    })
+}
+
+/// Name without `lib` and version of a library, like `music_module` for `libmusic_module.so.0.2.1`
+fn parse_library_name(path: &Path) -> Option<(&str, Option<Version>)> {
+    let file_name = path.file_name()?.to_str()?.strip_prefix("lib")?;
+    if let Some((name, version)) = file_name.split_once(".so.") {
+        return Some((name, Some(version.parse().ok()?)));
+    }
+    let stem = file_name.strip_suffix(".so")?;
+    match stem.rsplit_once('-') {
+        Some((name, version)) => match version.parse() {
+            Ok(version) => Some((name, Some(version))),
+            Err(_) => Some((stem, None)),
+        },
+        None => Some((stem, None)),
+    }
+}
+
+impl ModuleBuilderRef {
+    /// Load the module library at `path`, checking that it's compatible with this version of the abi
+    pub fn load_from_path(path: &Path) -> Result<Self, DiscoveryError> {
+        lib_header_from_path(path)
+            .and_then(|header| header.init_root_module::<ModuleBuilderRef>())
+            .map_err(|error| DiscoveryError::Load {
+                path: path.to_path_buf(),
+                error,
+            })
+    }
+    /// Find the library of the module `name` in the [`module_search_paths`] and load it
+    pub fn find_and_load(name: &str) -> Result<Self, DiscoveryError> {
+        let path = find_module(name, &module_search_paths())?;
+        Self::load_from_path(&path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_module_versions() {
+        let dir = std::env::temp_dir().join(format!("dynisland-discovery-{}", std::process::id()));
+        let versioned = dir.join("versioned");
+        let plain = dir.join("plain");
+        std::fs::create_dir_all(&versioned).unwrap();
+        std::fs::create_dir_all(&plain).unwrap();
+        for file in [
+            "libmusic_module.so.0.2.1",
+            "libmusic_module-0.10.0.so",
+            "libmusic_module.so.0.9.3",
+            "libother.so",
+            "notalib.txt",
+        ] {
+            std::fs::write(versioned.join(file), b"").unwrap();
+        }
+        std::fs::write(plain.join("libmusic_module.so"), b"").unwrap();
+        std::fs::write(plain.join("libmusic_module.so.9.0.0"), b"").unwrap();
+
+        let found = find_module("music-module", &[dir.join("missing"), versioned.clone()]).unwrap();
+        assert_eq!(found, versioned.join("libmusic_module-0.10.0.so"));
+        let found = find_module("music-module", &[plain.clone(), versioned.clone()]).unwrap();
+        assert_eq!(found, plain.join("libmusic_module.so"));
+        assert!(matches!(
+            find_module("clock", std::slice::from_ref(&versioned)),
+            Err(DiscoveryError::NotFound { .. })
+        ));
+        assert_eq!(
+            find_module_libraries(std::slice::from_ref(&versioned)).len(),
+            4
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
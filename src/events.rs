@@ -0,0 +1,458 @@
+use abi_stable::{
+    std_types::{ROption, RString, RVec},
+    StableAbi,
+};
+
+use crate::{
+    activity_mode::ModeTransition, geometry::SizeAbi, module::ActivityIdentifier, theme::ThemeInfo,
+};
+
+/// Network connectivity as seen by the host
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConnectivityState {
+    /// No network connection
+    Offline,
+    /// Connected to a network, but the internet is not reachable (captive portal, no route)
+    Limited,
+    /// Connected to the internet
+    Online {
+        /// The interface of the default route, like `wlan0`
+        interface: RString,
+        /// The ssid of the network, if the interface is wireless
+        ssid: ROption<RString>,
+    },
+}
+
+impl ConnectivityState {
+    pub fn is_online(&self) -> bool {
+        matches!(self, ConnectivityState::Online { .. })
+    }
+}
+
+/// An event from the compositor, forwarded by the host from its IPC connection (Hyprland, Sway, ...).
+///
+/// Delivered as `ModuleServerEvent::Compositor` to the modules subscribed to [`EventMask::COMPOSITOR`]
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CompositorEvent {
+    /// The active workspace of `monitor` (the connector name, like `DP-1`) changed
+    WorkspaceChanged {
+        monitor: RString,
+        workspace: RString,
+    },
+    /// A window received the keyboard focus
+    WindowFocused { title: RString, app_id: RString },
+    /// The focused window entered or left fullscreen
+    FullscreenToggled { fullscreen: bool },
+}
+
+/// A gesture performed by the user on the container of an activity, captured by the LayoutManager
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InteractionEvent {
+    /// Mouse button (1 is primary) was clicked `n_press` times in a row
+    Click { button: u32, n_press: i32 },
+    /// The activity was pressed and held
+    LongPress,
+    /// Scroll with the mouse wheel or touchpad, `dx` and `dy` are as reported by gtk
+    Scroll { dx: f64, dy: f64 },
+    /// Swipe with touch or touchpad, `velocity` is in pixels per second
+    Swipe {
+        direction: SwipeDirection,
+        velocity: f64,
+    },
+}
+
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SwipeDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Modifier keys held during a [`KeyEvent`], with the same bits as `gdk::ModifierType`
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyModifiers(u32);
+
+impl KeyModifiers {
+    pub const NONE: Self = Self(0);
+    pub const SHIFT: Self = Self(1);
+    pub const LOCK: Self = Self(1 << 1);
+    pub const CONTROL: Self = Self(1 << 2);
+    pub const ALT: Self = Self(1 << 3);
+    pub const SUPER: Self = Self(1 << 26);
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for KeyModifiers {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// A key press or release forwarded to an activity with an input grab,
+/// see `UIServerCommand::RequestInputGrab`
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyEvent {
+    /// Key symbol, like `gdk::Key::a`
+    pub keyval: u32,
+    /// Hardware keycode
+    pub keycode: u32,
+    pub modifiers: KeyModifiers,
+    /// true for a press (or a repeat), false for a release
+    pub pressed: bool,
+
+    /// Text produced by the key, if any
+    #[sabi(last_prefix_field)]
+    pub text: ROption<RString>,
+}
+
+/// What happened in a [`PointerEvent`]
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PointerEventKind {
+    /// Mouse button (1 is primary) pressed
+    Press {
+        button: u32,
+    },
+    Release {
+        button: u32,
+    },
+    Motion,
+    /// The pointer entered the activity
+    Enter,
+    /// The pointer left the activity, the position is the last one inside it
+    Leave,
+}
+
+/// A pointer event on the container of an activity, captured by the LayoutManager
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointerEvent {
+    pub kind: PointerEventKind,
+    /// Position in logical pixels relative to the top left corner of the activity
+    pub x: f64,
+    pub y: f64,
+
+    #[sabi(last_prefix_field)]
+    pub modifiers: KeyModifiers,
+}
+
+impl PointerEvent {
+    /// true if the position is inside a `width`x`height` area at the top left of the activity,
+    /// to implement custom hit areas
+    pub fn is_inside(&self, width: f64, height: f64) -> bool {
+        (0.0..width).contains(&self.x) && (0.0..height).contains(&self.y)
+    }
+}
+
+/// Whether the user can see an activity
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ActivityVisibility {
+    Shown,
+    /// Not rendered, like on a disabled monitor or in a collapsed group
+    Hidden,
+    /// Rendered but covered, like by a fullscreen window
+    Occluded,
+}
+
+impl ActivityVisibility {
+    /// false if updating the content of the activity is wasted work
+    pub fn is_visible(self) -> bool {
+        self == ActivityVisibility::Shown
+    }
+}
+
+/// A drag and drop over an activity registered with `UIServerCommand::SetDropTarget`,
+/// the positions are relative to the top left corner of the activity
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DropEvent {
+    /// A drag entered the activity, offering the data in `mime_types`
+    Enter {
+        mime_types: RVec<RString>,
+        x: f64,
+        y: f64,
+    },
+    Motion {
+        x: f64,
+        y: f64,
+    },
+    /// The drag left the activity without dropping
+    Leave,
+    /// The data was dropped, converted to `mime` (the first accepted type offered by the source)
+    Drop {
+        mime: RString,
+        data: RVec<u8>,
+        x: f64,
+        y: f64,
+    },
+}
+
+/// Where a shortcut registered with `UIServerCommand::RegisterShortcut` is active
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ShortcutScope {
+    /// Everywhere, through the global shortcuts of the compositor or portal
+    Global,
+    /// Only while a window of dynisland has the keyboard focus
+    #[default]
+    Bar,
+}
+
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChargingState {
+    Charging,
+    Discharging,
+    Full,
+    /// Plugged in but not charging, like when a charge limit is reached
+    NotCharging,
+    Unknown,
+}
+
+/// A change of the power state, from the UPower and power-profiles-daemon connection of the host
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PowerEvent {
+    /// The charge (from 0 to 100) or the state of the battery changed
+    BatteryChanged { percent: u8, state: ChargingState },
+    /// The power profile changed, like `power-saver`, `balanced` or `performance`
+    ProfileChanged { profile: RString },
+    /// The battery reached the low level configured in the system, modules should reduce their work
+    LowBattery { percent: u8 },
+}
+
+/// Activity of the user, from the idle-notify protocol binding of the host
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IdleEvent {
+    /// The user has been inactive for `since_ms` milliseconds, the threshold is configured in the host
+    UserIdle { since_ms: u64 },
+    /// The user is active again after a `UserIdle`
+    UserActive,
+}
+
+/// A change of the state of the host, sent to all the modules
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AppEvent {
+    /// Do not disturb was enabled or disabled,
+    /// notifications requested while it's enabled are not shown unless they have `bypass_dnd`
+    DoNotDisturbChanged(bool),
+    /// A window of dynisland (`window` is the name used in `ActivityMetadata::window_name`)
+    /// gained or lost the keyboard focus.
+    ///
+    /// Modules can grab the keyboard input only while the bar is focused
+    BarFocusChanged { window: RString, focused: bool },
+    /// The host is going to exit or restart in `grace_ms` milliseconds,
+    /// modules should save their persistent state now. `SabiModule::shutdown` is called at the end of the period
+    HostShuttingDown { grace_ms: u64 },
+}
+
+/// Event streams a module can subscribe to with `UIServerCommand::Subscribe`
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EventMask(u32);
+
+impl EventMask {
+    pub const NONE: Self = Self(0);
+    pub const COMPOSITOR: Self = Self(1);
+    pub const CONNECTIVITY: Self = Self(1 << 1);
+    pub const APP: Self = Self(1 << 2);
+    pub const POWER: Self = Self(1 << 3);
+    pub const IDLE: Self = Self(1 << 4);
+    pub const THEME: Self = Self(1 << 5);
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+    /// true if the subscription includes the stream of `event`
+    pub fn matches(self, event: &ModuleEvent) -> bool {
+        self.contains(event.mask())
+    }
+}
+
+impl std::ops::BitOr for EventMask {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// An event of a host stream, sent to the modules subscribed to it
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModuleEvent {
+    Compositor(CompositorEvent),
+    Connectivity(ConnectivityState),
+    App(AppEvent),
+    Power(PowerEvent),
+    Idle(IdleEvent),
+    ThemeChanged(ThemeInfo),
+}
+
+impl ModuleEvent {
+    /// The stream of the event
+    pub fn mask(&self) -> EventMask {
+        match self {
+            ModuleEvent::Compositor(_) => EventMask::COMPOSITOR,
+            ModuleEvent::Connectivity(_) => EventMask::CONNECTIVITY,
+            ModuleEvent::App(_) => EventMask::APP,
+            ModuleEvent::Power(_) => EventMask::POWER,
+            ModuleEvent::Idle(_) => EventMask::IDLE,
+            ModuleEvent::ThemeChanged(_) => EventMask::THEME,
+        }
+    }
+}
+
+impl From<ModuleEvent> for ModuleServerEvent {
+    fn from(event: ModuleEvent) -> Self {
+        match event {
+            ModuleEvent::Compositor(event) => ModuleServerEvent::Compositor(event),
+            ModuleEvent::Connectivity(state) => ModuleServerEvent::Connectivity(state),
+            ModuleEvent::App(event) => ModuleServerEvent::App(event),
+            ModuleEvent::Power(event) => ModuleServerEvent::Power(event),
+            ModuleEvent::Idle(event) => ModuleServerEvent::Idle(event),
+            ModuleEvent::ThemeChanged(event) => ModuleServerEvent::ThemeChanged(event),
+        }
+    }
+}
+
+/// An event from the host to a module, delivered with `SabiModule::handle_event`
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModuleServerEvent {
+    Compositor(CompositorEvent),
+    Connectivity(ConnectivityState),
+    App(AppEvent),
+    /// A gesture on an activity of the module
+    Interaction {
+        activity_id: ActivityIdentifier,
+        event: InteractionEvent,
+    },
+    /// A key event for an activity of the module with an input grab
+    Key {
+        activity_id: ActivityIdentifier,
+        event: KeyEvent,
+    },
+    /// A timer scheduled with `UIServerCommand::Schedule` fired
+    Timer {
+        token: u64,
+    },
+    /// A pointer event on an activity of the module
+    Pointer {
+        activity_id: ActivityIdentifier,
+        event: PointerEvent,
+    },
+    /// The LayoutManager is changing the mode of an activity of the module
+    ModeTransition {
+        activity_id: ActivityIdentifier,
+        transition: ModeTransition,
+    },
+    /// An activity of the module was shown, hidden or occluded.
+    ///
+    /// Modules can pause the updates and animations of the activities that are not visible
+    VisibilityChanged {
+        activity_id: ActivityIdentifier,
+        visibility: ActivityVisibility,
+    },
+    /// A drag and drop over an activity of the module
+    Drop {
+        activity_id: ActivityIdentifier,
+        event: DropEvent,
+    },
+    /// A shortcut registered by the module with `UIServerCommand::RegisterShortcut` was pressed
+    ShortcutPressed {
+        shortcut_id: RString,
+    },
+    /// The config file was modified and the section of the module changed,
+    /// sent before `SabiModule::update_config` is called with the same `section`.
+    ///
+    /// Modules can compare it with the current config to decide if their producers need a restart
+    ConfigFileChanged {
+        section: RString,
+    },
+    /// Sent to the modules subscribed to [`EventMask::POWER`]
+    Power(PowerEvent),
+    /// Sent to the modules subscribed to [`EventMask::IDLE`]
+    Idle(IdleEvent),
+    /// Sent to the modules subscribed to [`EventMask::THEME`]
+    ThemeChanged(ThemeInfo),
+    /// The user closed an activity of the module from the UI, the activity was already removed from the LayoutManager.
+    ///
+    /// The module must drop its references to the widget, it can stop the producer or add the activity again later
+    ActivityDismissedByUser {
+        activity_id: ActivityIdentifier,
+    },
+    /// The LayoutManager allocated a new size (in logical pixels) to an activity of the module
+    SizeAllocated {
+        activity_id: ActivityIdentifier,
+        size: SizeAbi,
+    },
+}
+
+impl ModuleServerEvent {
+    /// The activity the event is for, if any
+    pub fn activity_id(&self) -> Option<&ActivityIdentifier> {
+        match self {
+            ModuleServerEvent::Interaction { activity_id, .. }
+            | ModuleServerEvent::Key { activity_id, .. }
+            | ModuleServerEvent::Pointer { activity_id, .. }
+            | ModuleServerEvent::ModeTransition { activity_id, .. }
+            | ModuleServerEvent::VisibilityChanged { activity_id, .. }
+            | ModuleServerEvent::Drop { activity_id, .. }
+            | ModuleServerEvent::ActivityDismissedByUser { activity_id, .. }
+            | ModuleServerEvent::SizeAllocated { activity_id, .. } => Some(activity_id),
+            ModuleServerEvent::Compositor(_)
+            | ModuleServerEvent::Connectivity(_)
+            | ModuleServerEvent::App(_)
+            | ModuleServerEvent::Timer { .. }
+            | ModuleServerEvent::ShortcutPressed { .. }
+            | ModuleServerEvent::ConfigFileChanged { .. }
+            | ModuleServerEvent::Power(_)
+            | ModuleServerEvent::Idle(_)
+            | ModuleServerEvent::ThemeChanged(_) => None,
+        }
+    }
+}
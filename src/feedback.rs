@@ -0,0 +1,101 @@
+//! Errors of the commands that failed in the host, reported back to the module that sent them.
+//!
+//! The host gives every module a sender with `SabiModule::set_error_sender` right after creating it,
+//! the commands with a `reply` channel report their result there instead.
+
+use std::fmt::Display;
+
+use abi_stable::{
+    std_types::{ROption, RString},
+    StableAbi,
+};
+
+use crate::module::{ActivityIdentifier, UIServerCommand};
+
+/// Why a command failed
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CommandErrorKind {
+    /// `AddActivity` with an identifier that is already registered
+    DuplicateActivity,
+    /// The command refers to an activity that is not registered
+    UnknownActivity,
+    /// The host or the LayoutManager doesn't support the command
+    Unsupported,
+    /// Any other error, see the message
+    Failed,
+}
+
+impl Display for CommandErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandErrorKind::DuplicateActivity => write!(f, "the activity already exists"),
+            CommandErrorKind::UnknownActivity => write!(f, "the activity doesn't exist"),
+            CommandErrorKind::Unsupported => write!(f, "the command is not supported"),
+            CommandErrorKind::Failed => write!(f, "the command failed"),
+        }
+    }
+}
+
+/// A command that failed in the host
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommandError {
+    /// Name of the command, see `UIServerCommand::name`
+    pub command: RString,
+    /// The activity the command refers to, if any
+    pub activity_id: ROption<ActivityIdentifier>,
+    pub kind: CommandErrorKind,
+
+    /// Details for the logs, can be empty
+    #[sabi(last_prefix_field)]
+    pub message: RString,
+}
+
+impl CommandError {
+    /// Error of `command`, to be created by the host before consuming the command
+    pub fn for_command(command: &UIServerCommand, kind: CommandErrorKind, message: &str) -> Self {
+        Self {
+            command: command.name().into(),
+            activity_id: command.activity_id().cloned().into(),
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} failed: {}", self.command, self.kind)?;
+        if let ROption::RSome(id) = &self.activity_id {
+            write!(f, " ({id})")?;
+        }
+        if !self.message.is_empty() {
+            write!(f, ", {}", self.message)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for CommandError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_for_command() {
+        let command = UIServerCommand::RemoveActivity {
+            activity_id: ActivityIdentifier::new("music", "player"),
+            reply: ROption::RNone,
+        };
+        let error = CommandError::for_command(&command, CommandErrorKind::UnknownActivity, "");
+        assert_eq!(error.command, "RemoveActivity");
+        assert_eq!(
+            error.activity_id,
+            ROption::RSome(ActivityIdentifier::new("music", "player"))
+        );
+        assert!(error.to_string().starts_with("RemoveActivity failed"));
+    }
+}
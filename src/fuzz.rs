@@ -0,0 +1,621 @@
+//! Generators of adversarial command streams, to check that a host survives malformed or extreme
+//! command sequences (giant metadata, rapid add/remove cycles, odd strings, extreme values).
+//!
+//! The generators are deterministic for a given seed, so a failing sequence can be replayed.
+//!
+//! # Examples
+//! ```ignore
+//! let (app_send, app_recv) = abi_stable::external_types::crossbeam_channel::unbounded();
+//! // start the host on app_recv...
+//! let generator = CommandGenerator::new(FuzzConfig::default()).with_widget_factory(|| new_test_widget());
+//! let report = run_harness(&mut app_send.clone(), generator.take(100_000));
+//! assert!(report.error.is_none());
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+
+use abi_stable::{
+    external_types::crossbeam_channel::{unbounded, RSender},
+    std_types::{RBoxError, RHashMap, ROption, RString, RVec},
+};
+
+use crate::{
+    activity_mode::ActivityMode,
+    brightness::{BrightnessRequest, BrightnessValue},
+    events::{EventMask, ShortcutScope},
+    geometry::RectAbi,
+    logger::LogLevel,
+    main_context::main_task,
+    module::{ActivityIdentifier, ActivityMetadata, UIServerCommand},
+    notification::NotificationContent,
+    sound::SoundSpec,
+    surface::{
+        CursorKind, OutputSelector, OverlayLayer, PopupAnchor, Tooltip, WindowLayer, WindowProps,
+    },
+    SabiWidget,
+};
+
+/// Something that accepts the commands of a module, like the channel of the host
+pub trait CommandSink {
+    fn send_command(&mut self, command: UIServerCommand) -> Result<(), RBoxError>;
+}
+
+impl CommandSink for RSender<UIServerCommand> {
+    fn send_command(&mut self, command: UIServerCommand) -> Result<(), RBoxError> {
+        self.send(command)
+            .map_err(|_| RBoxError::from_fmt(&"the receiver was dropped"))
+    }
+}
+
+impl CommandSink for Vec<UIServerCommand> {
+    fn send_command(&mut self, command: UIServerCommand) -> Result<(), RBoxError> {
+        self.push(command);
+        Ok(())
+    }
+}
+
+/// Parameters of a [`CommandGenerator`]
+#[derive(Debug, Clone)]
+pub struct FuzzConfig {
+    pub seed: u64,
+    /// Module names used in the identifiers
+    pub modules: Vec<String>,
+    /// Number of different activity names for every module
+    pub activities_per_module: usize,
+    /// Maximum number of entries of the giant metadata updates
+    pub max_metadata_entries: usize,
+    /// Maximum length of the generated strings
+    pub max_string_len: usize,
+    /// Maximum nesting of the generated `Batch` commands
+    pub max_batch_depth: usize,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0x5eed,
+            modules: vec!["fuzz-module".to_string(), "".to_string()],
+            activities_per_module: 4,
+            max_metadata_entries: 10_000,
+            max_string_len: 64 * 1024,
+            max_batch_depth: 3,
+        }
+    }
+}
+
+/// Infinite iterator of adversarial commands
+///
+/// Every variant is generated, the ones with a widget (like `AddActivity`) only if a widget factory is set,
+/// every activity added is removed a few commands later.
+/// The receivers of the reply channels are dropped, so every reply fails
+pub struct CommandGenerator {
+    config: FuzzConfig,
+    rng_state: u64,
+    widget_factory: Option<Box<dyn FnMut() -> SabiWidget>>,
+    pending: VecDeque<UIServerCommand>,
+}
+
+impl CommandGenerator {
+    pub fn new(config: FuzzConfig) -> Self {
+        Self {
+            // xorshift must not start from 0
+            rng_state: config.seed | 1,
+            config,
+            widget_factory: None,
+            pending: VecDeque::new(),
+        }
+    }
+    /// Create the widgets for `AddActivity`, enables the add/remove cycles
+    pub fn with_widget_factory(mut self, factory: impl FnMut() -> SabiWidget + 'static) -> Self {
+        self.widget_factory = Some(Box::new(factory));
+        self
+    }
+
+    /// xorshift64
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng_state = x;
+        x
+    }
+    fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn string(&mut self) -> RString {
+        match self.below(6) {
+            0 => RString::new(),
+            1 => "ünïcödé 🏝️ \u{202e}rtl".into(),
+            2 => "<b>unclosed & \"markup\"".into(),
+            3 => "\0\n\t\r\u{1b}[31m".into(),
+            4 => {
+                let len = self.below(self.config.max_string_len + 1);
+                "x".repeat(len).into()
+            }
+            _ => format!("value-{}", self.next_u64()).into(),
+        }
+    }
+    fn activity_id(&mut self) -> ActivityIdentifier {
+        let module = match self.config.modules.len() {
+            0 => String::new(),
+            len => {
+                let idx = self.below(len);
+                self.config.modules[idx].clone()
+            }
+        };
+        let activity = match self.below(8) {
+            0 => self.string().into_string(),
+            _ => format!("activity-{}", self.below(self.config.activities_per_module)),
+        };
+        let mut id = ActivityIdentifier::new(&module, &activity);
+        if self.below(2) == 0 {
+            let window = self.string();
+            id.metadata_mut().set_window_name(&window);
+        }
+        id
+    }
+    fn timeout(&mut self) -> ROption<u64> {
+        match self.below(4) {
+            0 => ROption::RNone,
+            1 => ROption::RSome(0),
+            2 => ROption::RSome(u64::MAX),
+            _ => ROption::RSome(self.next_u64() % 10_000),
+        }
+    }
+
+    fn int(&mut self) -> i32 {
+        match self.below(4) {
+            0 => i32::MIN,
+            1 => i32::MAX,
+            2 => 0,
+            _ => (self.next_u64() % 2000) as i32 - 1000,
+        }
+    }
+    fn float(&mut self) -> f64 {
+        match self.below(5) {
+            0 => f64::NAN,
+            1 => f64::INFINITY,
+            2 => -1.0,
+            3 => f64::MAX,
+            _ => (self.next_u64() % 1001) as f64 / 1000.0,
+        }
+    }
+    fn flag(&mut self) -> bool {
+        self.below(2) == 0
+    }
+    fn strings(&mut self) -> RVec<RString> {
+        (0..self.below(16)).map(|_| self.string()).collect()
+    }
+    fn bytes(&mut self) -> RVec<u8> {
+        self.string().into_string().into_bytes().into()
+    }
+    fn widget(&mut self) -> Option<SabiWidget> {
+        self.widget_factory.as_mut().map(|factory| factory())
+    }
+    /// The receiver is dropped right away, so the host must survive a failed reply
+    fn reply<T>() -> RSender<T> {
+        unbounded().0
+    }
+    fn optional_reply<T>(&mut self) -> ROption<RSender<T>> {
+        match self.flag() {
+            true => ROption::RSome(Self::reply()),
+            false => ROption::RNone,
+        }
+    }
+    fn metadata(&mut self) -> ActivityMetadata {
+        let mut metadata = ActivityMetadata::new();
+        for _ in 0..self.below(16) {
+            let (key, value) = (self.string(), self.string());
+            metadata.set_additional_metadata(key.into_string(), value.into_string());
+        }
+        if self.flag() {
+            let weight = self.int();
+            metadata.set_order_weight(weight);
+        }
+        metadata
+    }
+    fn brightness_request(&mut self) -> BrightnessRequest {
+        let device = self.string();
+        match self.below(4) {
+            0 => BrightnessRequest::ListDevices {
+                reply: Self::reply(),
+            },
+            1 => BrightnessRequest::GetBrightness {
+                device,
+                reply: Self::reply(),
+            },
+            2 => BrightnessRequest::SetBrightness {
+                device,
+                value: match self.flag() {
+                    true => BrightnessValue::Absolute(self.next_u64() as u32),
+                    false => BrightnessValue::Percent(self.next_u64() as u8),
+                },
+                reply: self.optional_reply(),
+            },
+            _ => BrightnessRequest::Subscribe {
+                device: match self.flag() {
+                    true => ROption::RSome(device),
+                    false => ROption::RNone,
+                },
+                sender: Self::reply(),
+            },
+        }
+    }
+    fn output(&mut self) -> OutputSelector {
+        match self.below(4) {
+            0 => OutputSelector::Window(self.string()),
+            1 => OutputSelector::Monitor(self.string()),
+            2 => OutputSelector::Focused,
+            _ => OutputSelector::Primary,
+        }
+    }
+
+    fn generate(&mut self, depth: usize) -> UIServerCommand {
+        let activity_id = self.activity_id();
+        let module_name: RString = activity_id.module().into();
+        // one arm for every variant, in the order of the declaration
+        match self.below(47) {
+            0 => match self.widget() {
+                Some(widget) => {
+                    let remove = UIServerCommand::RemoveActivity {
+                        activity_id: activity_id.clone(),
+                        reply: ROption::RNone,
+                    };
+                    let at = self.below(self.pending.len() + 1);
+                    self.pending.insert(at, remove);
+                    UIServerCommand::AddActivity {
+                        activity_id,
+                        widget,
+                        reply: self.optional_reply(),
+                    }
+                }
+                None => UIServerCommand::RemoveActivity {
+                    activity_id,
+                    reply: ROption::RNone,
+                },
+            },
+            1 => UIServerCommand::RemoveActivity {
+                activity_id,
+                reply: self.optional_reply(),
+            },
+            2 => UIServerCommand::RestartProducers { module_name },
+            3 => UIServerCommand::RequestNotification {
+                activity_id,
+                mode: ActivityMode::ALL[self.below(ActivityMode::ALL.len())],
+                duration: self.timeout(),
+                bypass_dnd: self.flag(),
+            },
+            4 => UIServerCommand::Brightness {
+                request: self.brightness_request(),
+            },
+            5 => {
+                let entries = self.below(self.config.max_metadata_entries + 1);
+                let mut set = RHashMap::with_capacity(entries);
+                for i in 0..entries {
+                    set.insert(format!("key-{i}").into(), self.string());
+                }
+                UIServerCommand::UpdateMetadataBulk {
+                    activity_id,
+                    set,
+                    remove: self.strings(),
+                }
+            }
+            6 => UIServerCommand::SetCursorHint {
+                activity_id,
+                cursor: match self.below(3) {
+                    0 => CursorKind::Default,
+                    1 => CursorKind::Pointer,
+                    _ => CursorKind::Named(self.string()),
+                },
+            },
+            7 => UIServerCommand::SetLayer {
+                activity_id,
+                layer: [
+                    OverlayLayer::Normal,
+                    OverlayLayer::Raised,
+                    OverlayLayer::Overlay,
+                ][self.below(3)],
+                timeout: self.timeout(),
+            },
+            8 => {
+                let tooltip = match self.below(3) {
+                    0 => ROption::RNone,
+                    1 => ROption::RSome(Tooltip::plain(&self.string())),
+                    _ => ROption::RSome(Tooltip::markup(&self.string()).with_icon(&self.string())),
+                };
+                UIServerCommand::SetTooltip {
+                    activity_id,
+                    tooltip,
+                }
+            }
+            9 => UIServerCommand::QueryDoNotDisturb {
+                reply: Self::reply(),
+            },
+            10 => UIServerCommand::RegisterProducerThread {
+                module_name,
+                thread_id: self.int(),
+            },
+            11 => UIServerCommand::RequestInputGrab {
+                activity_id,
+                reply: self.optional_reply(),
+            },
+            12 => UIServerCommand::ReleaseInputGrab { activity_id },
+            13 => UIServerCommand::UpdateActivityMetadata {
+                activity_id,
+                metadata: self.metadata(),
+            },
+            14 => UIServerCommand::SetActivityVisibility {
+                activity_id,
+                visible: self.flag(),
+            },
+            15 => UIServerCommand::RequestFocus { activity_id },
+            16 => UIServerCommand::SetActivityOrder {
+                activity_id,
+                weight: self.int(),
+            },
+            17 if depth < self.config.max_batch_depth => UIServerCommand::Batch(
+                (0..self.below(8))
+                    .map(|_| self.generate(depth + 1))
+                    .collect(),
+            ),
+            17 | 18 => UIServerCommand::RequestConfigReload { module_name },
+            19 => UIServerCommand::Log {
+                module_name,
+                level: [
+                    LogLevel::Error,
+                    LogLevel::Warn,
+                    LogLevel::Info,
+                    LogLevel::Debug,
+                    LogLevel::Trace,
+                ][self.below(5)],
+                message: self.string(),
+            },
+            20 => UIServerCommand::QueryActivities {
+                module_name,
+                reply: Self::reply(),
+            },
+            21 => UIServerCommand::ShowNotification {
+                activity_id,
+                content: NotificationContent::new(&self.string())
+                    .body(&self.string())
+                    .action(&self.string(), &self.string()),
+            },
+            22 => UIServerCommand::DismissNotification { activity_id },
+            23 => match self.widget() {
+                Some(widget) => UIServerCommand::OpenPopup {
+                    activity_id,
+                    widget,
+                    anchor: match self.flag() {
+                        true => PopupAnchor::Below,
+                        false => PopupAnchor::Rect(RectAbi::new(
+                            self.int(),
+                            self.int(),
+                            self.int(),
+                            self.int(),
+                        )),
+                    },
+                },
+                None => UIServerCommand::ClosePopup { activity_id },
+            },
+            24 => UIServerCommand::ClosePopup { activity_id },
+            25 => UIServerCommand::MoveActivity {
+                activity_id,
+                target: self.output(),
+            },
+            26 => UIServerCommand::SetUrgency {
+                activity_id,
+                urgent: self.flag(),
+            },
+            27 => match self.widget() {
+                Some(widget) => UIServerCommand::ReplaceActivityWidget {
+                    activity_id,
+                    widget,
+                },
+                None => UIServerCommand::RequestFocus { activity_id },
+            },
+            28 => UIServerCommand::RenameActivity {
+                old_id: activity_id,
+                new_name: self.string(),
+            },
+            29 => UIServerCommand::CaptureActivity {
+                activity_id,
+                reply: Self::reply(),
+            },
+            30 => {
+                let sound = match self.flag() {
+                    true => SoundSpec::event(&self.string()),
+                    false => SoundSpec::file(&self.string()),
+                };
+                UIServerCommand::PlaySound {
+                    sound: sound.with_volume(self.float() as f32),
+                }
+            }
+            31 => UIServerCommand::SetActivityCssClasses {
+                activity_id,
+                classes: self.strings(),
+            },
+            32 => UIServerCommand::UnloadModule { module_name },
+            33 => UIServerCommand::RequestExclusiveZone {
+                activity_id,
+                size: match self.flag() {
+                    true => ROption::RSome(self.int()),
+                    false => ROption::RNone,
+                },
+            },
+            34 => UIServerCommand::RequestWindowProperties {
+                window: self.string(),
+                props: WindowProps::default().layer(
+                    [
+                        WindowLayer::Background,
+                        WindowLayer::Bottom,
+                        WindowLayer::Top,
+                        WindowLayer::Overlay,
+                    ][self.below(4)],
+                ),
+            },
+            35 => UIServerCommand::Subscribe {
+                module_name,
+                events: EventMask::from_bits(self.next_u64() as u32),
+                sender: Self::reply(),
+            },
+            36 => UIServerCommand::SendToModule {
+                target_module: module_name,
+                payload: self.bytes(),
+                reply: self.optional_reply(),
+            },
+            37 => UIServerCommand::Schedule {
+                module_name,
+                token: self.next_u64(),
+                after_ms: self.timeout().unwrap_or(0),
+                repeat: self.flag(),
+            },
+            38 => UIServerCommand::OpenUri { uri: self.string() },
+            39 => UIServerCommand::LaunchApp {
+                desktop_id: self.string(),
+                args: self.strings(),
+            },
+            40 => UIServerCommand::ClipboardSet {
+                mime: self.string(),
+                data: self.bytes(),
+            },
+            41 => UIServerCommand::ClipboardGet {
+                mime: self.string(),
+                reply: Self::reply(),
+            },
+            42 => UIServerCommand::ReportProgress {
+                activity_id,
+                token: self.next_u64(),
+                fraction: self.float(),
+                label: match self.flag() {
+                    true => ROption::RSome(self.string()),
+                    false => ROption::RNone,
+                },
+            },
+            43 => UIServerCommand::SetDropTarget {
+                activity_id,
+                mime_types: self.strings(),
+            },
+            44 => UIServerCommand::RegisterShortcut {
+                module_name,
+                shortcut_id: self.string(),
+                trigger: self.string(),
+                scope: match self.flag() {
+                    true => ShortcutScope::Global,
+                    false => ShortcutScope::Bar,
+                },
+            },
+            45 => UIServerCommand::UnregisterShortcut {
+                module_name,
+                shortcut_id: self.string(),
+            },
+            _ => UIServerCommand::RunOnMain {
+                task: main_task(|| {}),
+            },
+        }
+    }
+}
+
+impl Iterator for CommandGenerator {
+    type Item = UIServerCommand;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.below(3) == 0 {
+            if let Some(command) = self.pending.pop_front() {
+                return Some(command);
+            }
+        }
+        Some(self.generate(0))
+    }
+}
+
+/// Result of [`run_harness`]
+#[derive(Debug, Default)]
+pub struct HarnessReport {
+    /// Number of commands accepted by the sink
+    pub sent: usize,
+    /// Number of commands accepted for every variant
+    pub by_variant: BTreeMap<&'static str, usize>,
+    /// The error that stopped the harness, if any
+    pub error: Option<RBoxError>,
+}
+
+/// Send all the `commands` to `sink`, stopping at the first error
+pub fn run_harness(
+    sink: &mut impl CommandSink,
+    commands: impl IntoIterator<Item = UIServerCommand>,
+) -> HarnessReport {
+    let mut report = HarnessReport::default();
+    for command in commands {
+        let name = command.name();
+        if let Err(err) = sink.send_command(command) {
+            report.error = Some(err);
+            break;
+        }
+        report.sent += 1;
+        *report.by_variant.entry(name).or_default() += 1;
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use abi_stable::{type_layout::TLData, StableAbi};
+
+    use super::*;
+
+    #[test]
+    fn test_harness_deterministic() {
+        let config = FuzzConfig {
+            max_metadata_entries: 50,
+            max_string_len: 256,
+            ..Default::default()
+        };
+        let mut first = Vec::new();
+        let report = run_harness(&mut first, CommandGenerator::new(config.clone()).take(500));
+        assert_eq!(report.sent, 500);
+        assert!(report.error.is_none());
+        assert!(!report.by_variant.contains_key("AddActivity"));
+        assert!(report.by_variant.len() > 5);
+
+        let mut second = Vec::new();
+        run_harness(&mut second, CommandGenerator::new(config).take(500));
+        let names =
+            |commands: &[UIServerCommand]| commands.iter().map(|c| c.name()).collect::<Vec<_>>();
+        assert_eq!(names(&first), names(&second));
+    }
+
+    fn batch_depth(command: &UIServerCommand) -> usize {
+        match command {
+            UIServerCommand::Batch(commands) => {
+                1 + commands.iter().map(batch_depth).max().unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_generator_covers_all_variants() {
+        let config = FuzzConfig {
+            max_metadata_entries: 8,
+            max_string_len: 16,
+            max_batch_depth: 2,
+            ..Default::default()
+        };
+        let generator = CommandGenerator::new(config).with_widget_factory(|| SabiWidget {
+            widget_ref: std::ptr::null_mut(),
+        });
+        let mut commands = Vec::new();
+        let report = run_harness(&mut commands, generator.take(5000));
+        let TLData::Enum(tl_enum) = <UIServerCommand as StableAbi>::LAYOUT.data() else {
+            panic!("UIServerCommand is not an enum");
+        };
+        assert_eq!(report.by_variant.len(), tl_enum.variant_count());
+        assert!(commands.iter().all(|command| batch_depth(command) <= 2));
+        assert!(commands.iter().any(|command| batch_depth(command) == 2));
+    }
+}
@@ -0,0 +1,231 @@
+use std::ops::{Add, Sub};
+
+use abi_stable::StableAbi;
+
+/// A point in logical pixels
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointAbi {
+    pub x: i32,
+
+    #[sabi(last_prefix_field)]
+    pub y: i32,
+}
+
+/// A size in logical pixels
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SizeAbi {
+    pub width: i32,
+
+    #[sabi(last_prefix_field)]
+    pub height: i32,
+}
+
+/// A rectangle in logical pixels, `origin` is the top left corner
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RectAbi {
+    pub origin: PointAbi,
+
+    #[sabi(last_prefix_field)]
+    pub size: SizeAbi,
+}
+
+impl PointAbi {
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Add for PointAbi {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for PointAbi {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SizeAbi {
+    pub const fn new(width: i32, height: i32) -> Self {
+        Self { width, height }
+    }
+    /// `true` if the width or the height is not positive
+    pub const fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+    pub const fn area(&self) -> i64 {
+        if self.is_empty() {
+            return 0;
+        }
+        self.width as i64 * self.height as i64
+    }
+    /// Keep both dimensions between `min` and `max`
+    pub fn clamp(self, min: SizeAbi, max: SizeAbi) -> Self {
+        Self::new(
+            self.width.clamp(min.width, max.width.max(min.width)),
+            self.height.clamp(min.height, max.height.max(min.height)),
+        )
+    }
+}
+
+impl Add for SizeAbi {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.width + rhs.width, self.height + rhs.height)
+    }
+}
+
+impl Sub for SizeAbi {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.width - rhs.width, self.height - rhs.height)
+    }
+}
+
+impl RectAbi {
+    pub const fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            origin: PointAbi::new(x, y),
+            size: SizeAbi::new(width, height),
+        }
+    }
+    pub const fn from_parts(origin: PointAbi, size: SizeAbi) -> Self {
+        Self { origin, size }
+    }
+    pub const fn right(&self) -> i32 {
+        self.origin.x + self.size.width
+    }
+    pub const fn bottom(&self) -> i32 {
+        self.origin.y + self.size.height
+    }
+    pub const fn center(&self) -> PointAbi {
+        PointAbi::new(
+            self.origin.x + self.size.width / 2,
+            self.origin.y + self.size.height / 2,
+        )
+    }
+    /// `true` if `point` is inside the rectangle, the right and bottom edges are excluded
+    pub const fn contains(&self, point: PointAbi) -> bool {
+        point.x >= self.origin.x
+            && point.y >= self.origin.y
+            && point.x < self.right()
+            && point.y < self.bottom()
+    }
+    /// Move the rectangle by `offset`
+    pub fn translate(self, offset: PointAbi) -> Self {
+        Self::from_parts(self.origin + offset, self.size)
+    }
+    /// The area shared by both rectangles, `None` if they don't overlap
+    pub fn intersection(&self, other: &RectAbi) -> Option<RectAbi> {
+        let x = self.origin.x.max(other.origin.x);
+        let y = self.origin.y.max(other.origin.y);
+        let rect = RectAbi::new(
+            x,
+            y,
+            self.right().min(other.right()) - x,
+            self.bottom().min(other.bottom()) - y,
+        );
+        (!rect.size.is_empty()).then_some(rect)
+    }
+    /// The smallest rectangle containing both rectangles
+    pub fn union(&self, other: &RectAbi) -> RectAbi {
+        let x = self.origin.x.min(other.origin.x);
+        let y = self.origin.y.min(other.origin.y);
+        RectAbi::new(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+}
+
+#[cfg(feature = "gtk")]
+mod gtk_conversions {
+    use gtk::graphene;
+
+    use super::{PointAbi, RectAbi, SizeAbi};
+
+    impl From<gdk::Rectangle> for RectAbi {
+        fn from(rect: gdk::Rectangle) -> Self {
+            RectAbi::new(rect.x(), rect.y(), rect.width(), rect.height())
+        }
+    }
+    impl From<RectAbi> for gdk::Rectangle {
+        fn from(rect: RectAbi) -> Self {
+            gdk::Rectangle::new(
+                rect.origin.x,
+                rect.origin.y,
+                rect.size.width,
+                rect.size.height,
+            )
+        }
+    }
+    impl From<RectAbi> for graphene::Rect {
+        fn from(rect: RectAbi) -> Self {
+            graphene::Rect::new(
+                rect.origin.x as f32,
+                rect.origin.y as f32,
+                rect.size.width as f32,
+                rect.size.height as f32,
+            )
+        }
+    }
+    impl From<PointAbi> for graphene::Point {
+        fn from(point: PointAbi) -> Self {
+            graphene::Point::new(point.x as f32, point.y as f32)
+        }
+    }
+    /// Coordinates are rounded to the nearest pixel
+    impl From<graphene::Point> for PointAbi {
+        fn from(point: graphene::Point) -> Self {
+            PointAbi::new(point.x().round() as i32, point.y().round() as i32)
+        }
+    }
+    impl From<SizeAbi> for graphene::Size {
+        fn from(size: SizeAbi) -> Self {
+            graphene::Size::new(size.width as f32, size.height as f32)
+        }
+    }
+    /// Dimensions are rounded to the nearest pixel
+    impl From<graphene::Size> for SizeAbi {
+        fn from(size: graphene::Size) -> Self {
+            SizeAbi::new(size.width().round() as i32, size.height().round() as i32)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rect_operations() {
+        let a = RectAbi::new(0, 0, 100, 50);
+        let b = RectAbi::new(50, 25, 100, 50);
+        assert_eq!(a.intersection(&b), Some(RectAbi::new(50, 25, 50, 25)));
+        assert_eq!(a.union(&b), RectAbi::new(0, 0, 150, 75));
+        assert_eq!(a.intersection(&RectAbi::new(100, 0, 10, 10)), None);
+        assert!(a.contains(PointAbi::new(99, 49)));
+        assert!(!a.contains(PointAbi::new(100, 49)));
+        assert_eq!(a.center(), PointAbi::new(50, 25));
+        assert_eq!(
+            a.translate(PointAbi::new(10, -10)),
+            RectAbi::new(10, -10, 100, 50)
+        );
+        assert_eq!(
+            SizeAbi::new(500, 5).clamp(SizeAbi::new(10, 10), SizeAbi::new(200, 200)),
+            SizeAbi::new(200, 10)
+        );
+    }
+}
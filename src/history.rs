@@ -0,0 +1,119 @@
+use abi_stable::{
+    std_types::{ROption, RString, RVec},
+    StableAbi,
+};
+
+use crate::module::{ActivityIdentifier, UIServerCommand};
+
+/// Summary of a command sent by a module, widgets and channels are not kept
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+pub struct CommandRecord {
+    /// Name of the `UIServerCommand` variant
+    pub command: RString,
+    pub activity_id: ROption<ActivityIdentifier>,
+
+    /// Milliseconds since the unix epoch when the host received the command
+    #[sabi(last_prefix_field)]
+    pub timestamp_ms: u64,
+}
+
+impl CommandRecord {
+    pub fn from_command(command: &UIServerCommand) -> Self {
+        let timestamp_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_millis() as u64)
+            .unwrap_or_default();
+        Self {
+            command: command.name().into(),
+            activity_id: command.activity_id().cloned().into(),
+            timestamp_ms,
+        }
+    }
+}
+
+/// Ring buffer of the last commands sent by a module, maintained by the host
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandHistory {
+    capacity: u32,
+    /// Index of the oldest record once the buffer is full
+    start: u32,
+
+    #[sabi(last_prefix_field)]
+    records: RVec<CommandRecord>,
+}
+
+impl CommandHistory {
+    pub fn new(capacity: u32) -> Self {
+        Self {
+            capacity,
+            start: 0,
+            records: RVec::with_capacity(capacity as usize),
+        }
+    }
+    /// Add a record, dropping the oldest one if the history is full
+    pub fn push(&mut self, record: CommandRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() < self.capacity as usize {
+            self.records.push(record);
+        } else {
+            self.records[self.start as usize] = record;
+            self.start = (self.start + 1) % self.capacity;
+        }
+    }
+    pub fn record(&mut self, command: &UIServerCommand) {
+        self.push(CommandRecord::from_command(command));
+    }
+    /// Iterate from the oldest to the newest record
+    pub fn iter(&self) -> impl Iterator<Item = &CommandRecord> {
+        let (newest, oldest) = self.records.split_at(self.start as usize);
+        oldest.iter().chain(newest.iter())
+    }
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_command_history_ring() {
+        let mut history = CommandHistory::new(3);
+        for i in 0..5 {
+            history.push(CommandRecord {
+                command: "RestartProducers".into(),
+                activity_id: ROption::RNone,
+                timestamp_ms: i,
+            });
+        }
+        assert_eq!(history.len(), 3);
+        let timestamps: Vec<_> = history.iter().map(|record| record.timestamp_ms).collect();
+        assert_eq!(timestamps, vec![2, 3, 4]);
+
+        let activity_id = ActivityIdentifier::new("module", "activity");
+        history.record(&UIServerCommand::RemoveActivity {
+            activity_id: activity_id.clone(),
+            reply: ROption::RNone,
+        });
+        let newest = history.iter().last().unwrap();
+        assert_eq!(newest.command, "RemoveActivity");
+        assert_eq!(newest.activity_id, ROption::RSome(activity_id));
+
+        let mut history = CommandHistory::new(0);
+        history.record(&UIServerCommand::RestartProducers {
+            module_name: "module".into(),
+        });
+        assert!(history.is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+use abi_stable::{
+    prefix_type::PrefixTypeTrait,
+    std_types::{RSlice, RStr},
+    StableAbi,
+};
+
+/// Name of the brightness service, see [`crate::brightness::BrightnessRequest`]
+pub const SERVICE_BRIGHTNESS: &str = "brightness";
+/// Name of the compositor events service, see `SabiModule::on_compositor_event`
+pub const SERVICE_COMPOSITOR_EVENTS: &str = "compositor-events";
+/// Name of the connectivity events service, see `SabiModule::on_connectivity_changed`
+pub const SERVICE_CONNECTIVITY_EVENTS: &str = "connectivity-events";
+/// Name of the power events service, see [`crate::events::PowerEvent`]
+pub const SERVICE_POWER_EVENTS: &str = "power-events";
+
+/// Optional feature: the host delivers events through the event bus, see `ModuleBuilder::negotiate`
+pub const FEATURE_EVENT_BUS: &str = "event-bus";
+/// Optional feature: the host provides a persistent storage service
+pub const FEATURE_STORAGE: &str = "storage";
+/// Optional feature: the host sends events back to the module with `SabiModule::handle_event`
+pub const FEATURE_REVERSE_CHANNEL: &str = "reverse-channel";
+
+/// Information about the host given to a module when it's created with `ModuleBuilder::new_with_context`
+///
+/// Modules should use the directories from here instead of computing the XDG paths themselves,
+/// the host may override them (for example when running tests or multiple instances).
+///
+/// The context lives for the whole life of the host, so all the strings are `'static`,
+/// the host can create them with [`leak_str`]
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = HostContextRef)))]
+#[sabi(missing_field(panic))]
+pub struct HostContext {
+    /// Version of dynisland
+    pub host_version: RStr<'static>,
+    /// Directory of the dynisland config, like `~/.config/dynisland`
+    pub config_dir: RStr<'static>,
+    /// Directory for cached data, like `~/.cache/dynisland`
+    pub cache_dir: RStr<'static>,
+    /// Directory for sockets and other runtime files, like `$XDG_RUNTIME_DIR/dynisland`
+    pub runtime_dir: RStr<'static>,
+
+    /// Names of the optional services provided by the host, like [`SERVICE_BRIGHTNESS`].
+    ///
+    /// The services are used with their commands and events, this only tells which ones are available
+    #[sabi(last_prefix_field)]
+    pub service_names: RSlice<'static, RStr<'static>>,
+}
+
+impl HostContext {
+    /// Leak the context to get a reference that can be passed to the modules
+    pub fn leak(self) -> HostContextRef {
+        self.leak_into_prefix()
+    }
+}
+
+/// Leak `value` to use it in a [`HostContext`]
+pub fn leak_str(value: String) -> RStr<'static> {
+    RStr::from_str(Box::leak(value.into_boxed_str()))
+}
+
+impl HostContextRef {
+    /// Check if `name` is in the [`service_names`](HostContext::service_names) of the host
+    pub fn has_service(&self, name: RStr<'_>) -> bool {
+        self.service_names().as_slice().contains(&name)
+    }
+    /// Cache directory reserved for the module `module_name`, it may not exist yet
+    pub fn module_cache_dir(&self, module_name: RStr<'_>) -> String {
+        format!("{}/{}", self.cache_dir(), module_name)
+    }
+    /// Runtime directory reserved for the module `module_name`, it may not exist yet
+    pub fn module_runtime_dir(&self, module_name: RStr<'_>) -> String {
+        format!("{}/{}", self.runtime_dir(), module_name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_host_context() {
+        static SERVICES: [RStr<'static>; 1] = [RStr::from_str(SERVICE_BRIGHTNESS)];
+        let ctx = HostContext {
+            host_version: RStr::from_str("0.2.0"),
+            config_dir: RStr::from_str("/config"),
+            cache_dir: RStr::from_str("/cache"),
+            runtime_dir: RStr::from_str("/run"),
+            service_names: RSlice::from_slice(&SERVICES),
+        }
+        .leak();
+        assert!(ctx.has_service(RStr::from_str(SERVICE_BRIGHTNESS)));
+        assert!(!ctx.has_service(RStr::from_str(SERVICE_POWER_EVENTS)));
+        assert_eq!(
+            ctx.module_cache_dir(RStr::from_str("music")),
+            "/cache/music"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Integrity checks of module libraries, for locked-down setups.
+//!
+//! A module can declare a `checksum` (the hex encoded SHA-256 of its build, usually set by the build
+//! or packaging scripts) and a `signature` of [`signed_message`] in its `ModuleBuilder`.
+//! The host checks them with [`ModuleBuilderRef::verify_integrity`] before calling `new`,
+//! refusing the modules that are not trusted by the [`IntegrityPolicy`].
+//!
+//! This crate doesn't implement any cryptography, the host brings the signature verification.
+//! The fields are read after the library is loaded, a host that must not run any untrusted code
+//! should also check the library file before opening it.
+
+use std::fmt::Display;
+
+use abi_stable::std_types::ROption;
+
+use crate::module::ModuleBuilderRef;
+
+/// Verify `signature` (the second argument) of `message` (the first argument)
+pub type SignatureVerifier = dyn Fn(&[u8], &[u8]) -> bool;
+
+/// Which modules the host accepts
+pub struct IntegrityPolicy<'a> {
+    /// Accept only the modules with one of these checksums, any checksum is accepted if it's empty
+    pub trusted_checksums: Vec<String>,
+    /// Refuse the modules without a checksum
+    pub require_checksum: bool,
+    /// Verify the signature of [`signed_message`], if set every module must be signed
+    pub verify_signature: Option<&'a SignatureVerifier>,
+}
+
+impl<'a> IntegrityPolicy<'a> {
+    /// Accept every module
+    pub fn permissive() -> Self {
+        Self {
+            trusted_checksums: Vec::new(),
+            require_checksum: false,
+            verify_signature: None,
+        }
+    }
+}
+
+/// Reason a module was refused by an [`IntegrityPolicy`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    MissingChecksum,
+    UntrustedChecksum(String),
+    MissingSignature,
+    InvalidSignature,
+}
+
+impl Display for IntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegrityError::MissingChecksum => write!(f, "the module doesn't declare a checksum"),
+            IntegrityError::UntrustedChecksum(checksum) => {
+                write!(f, "the checksum {checksum} is not trusted")
+            }
+            IntegrityError::MissingSignature => write!(f, "the module is not signed"),
+            IntegrityError::InvalidSignature => write!(f, "the signature of the module is invalid"),
+        }
+    }
+}
+impl std::error::Error for IntegrityError {}
+
+/// The message a module must sign: name, version and checksum separated by `\0`
+pub fn signed_message(name: &str, version: &str, checksum: &str) -> Vec<u8> {
+    [name, version, checksum].join("\0").into_bytes()
+}
+
+impl ModuleBuilderRef {
+    /// Check the `checksum` and `signature` of the module against `policy`
+    pub fn verify_integrity(&self, policy: &IntegrityPolicy) -> Result<(), IntegrityError> {
+        let checksum = match self.checksum() {
+            ROption::RSome(checksum) if !checksum.is_empty() => Some(checksum.as_str()),
+            _ => None,
+        };
+        match checksum {
+            None if policy.require_checksum
+                || !policy.trusted_checksums.is_empty()
+                || policy.verify_signature.is_some() =>
+            {
+                return Err(IntegrityError::MissingChecksum)
+            }
+            Some(checksum)
+                if !policy.trusted_checksums.is_empty()
+                    && !policy
+                        .trusted_checksums
+                        .iter()
+                        .any(|trusted| trusted.eq_ignore_ascii_case(checksum)) =>
+            {
+                return Err(IntegrityError::UntrustedChecksum(checksum.to_string()))
+            }
+            _ => {}
+        }
+        if let Some(verify) = policy.verify_signature {
+            let ROption::RSome(signature) = self.signature() else {
+                return Err(IntegrityError::MissingSignature);
+            };
+            let message = signed_message(
+                self.name().as_str(),
+                self.version().as_str(),
+                checksum.unwrap_or_default(),
+            );
+            if !verify(&message, signature.as_slice()) {
+                return Err(IntegrityError::InvalidSignature);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use abi_stable::{
+        external_types::crossbeam_channel::RSender,
+        prefix_type::PrefixTypeTrait,
+        std_types::{RBoxError, RResult, RSlice, RStr},
+    };
+
+    use super::*;
+    use crate::module::{ModuleBuilder, ModuleType, UIServerCommand};
+
+    extern "C" fn new(_app_send: RSender<UIServerCommand>) -> RResult<ModuleType, RBoxError> {
+        RResult::RErr(RBoxError::from_fmt(&"unused"))
+    }
+
+    #[test]
+    fn test_verify_integrity() {
+        let unsigned = ModuleBuilder {
+            checksum: ROption::RSome(RStr::from_str("ABCD")),
+            ..ModuleBuilder::with_defaults(new, "test")
+        }
+        .leak_into_prefix();
+        let signed = ModuleBuilder {
+            checksum: ROption::RSome(RStr::from_str("abcd")),
+            signature: ROption::RSome(RSlice::from_slice(b"test\0\0abcd")),
+            ..ModuleBuilder::with_defaults(new, "test")
+        }
+        .leak_into_prefix();
+        let empty = ModuleBuilder::with_defaults(new, "test").leak_into_prefix();
+
+        assert!(empty
+            .verify_integrity(&IntegrityPolicy::permissive())
+            .is_ok());
+        let mut policy = IntegrityPolicy::permissive();
+        policy.trusted_checksums.push("abcd".to_string());
+        assert!(unsigned.verify_integrity(&policy).is_ok());
+        assert_eq!(
+            empty.verify_integrity(&policy),
+            Err(IntegrityError::MissingChecksum)
+        );
+        // toy verifier, the signature is the message itself
+        let verify = |message: &[u8], signature: &[u8]| message == signature;
+        policy.verify_signature = Some(&verify);
+        assert!(signed.verify_integrity(&policy).is_ok());
+        assert_eq!(
+            unsigned.verify_integrity(&policy),
+            Err(IntegrityError::MissingSignature)
+        );
+        policy.trusted_checksums = vec!["ffff".to_string()];
+        assert!(matches!(
+            signed.verify_integrity(&policy),
+            Err(IntegrityError::UntrustedChecksum(_))
+        ));
+    }
+}
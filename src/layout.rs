@@ -1,5 +1,6 @@
 use abi_stable::{
     declare_root_module_statics,
+    external_types::crossbeam_channel::RSender,
     library::RootModule,
     package_version_strings, sabi_trait,
     sabi_types::VersionStrings,
@@ -7,7 +8,13 @@ use abi_stable::{
     StableAbi,
 };
 
-use crate::{module::ActivityIdentifier, NotImplementedError, SabiApplication, SabiWidget};
+use crate::{
+    activity_mode::ActivityMode,
+    events::ModuleServerEvent,
+    module::ActivityIdentifier,
+    surface::{EncodedImage, OutputSelector, PopupAnchor},
+    NotImplementedError, SabiApplication, SabiWidget,
+};
 
 pub type LayoutManagerType = SabiLayoutManager_TO<'static, RBox<()>>;
 
@@ -24,7 +31,7 @@ pub trait SabiLayoutManager {
     fn activity_notification(
         &self,
         activity: &ActivityIdentifier,
-        mode_id: u8,
+        mode: ActivityMode,
         duration: ROption<u64>,
     );
     fn list_windows(&self) -> RVec<RString> {
@@ -34,6 +41,104 @@ pub trait SabiLayoutManager {
     fn cli_command(&self, _command: RString) -> RResult<RString, RBoxError> {
         RResult::RErr(RBoxError::new(NotImplementedError::default()))
     }
+
+    /// Called by the host after the metadata of an activity was updated by its module,
+    /// `activity_id` contains the new metadata
+    fn activity_metadata_changed(&mut self, _activity_id: &ActivityIdentifier) {}
+
+    /// Show or hide an activity, hidden activities must keep their position.
+    /// Without an implementation the activities are always visible
+    fn set_activity_visibility(&mut self, _activity_id: &ActivityIdentifier, _visible: bool) {}
+
+    /// A module asked to bring the attention of the user to an activity,
+    /// the LayoutManager can expand or raise it, or ignore the request
+    fn request_focus(&mut self, _activity_id: &ActivityIdentifier) {}
+
+    /// Ordering hint for an activity, higher weights come first
+    fn set_activity_order(&mut self, _activity_id: &ActivityIdentifier, _weight: i32) {}
+
+    /// Show `widget` in a popup anchored to the activity, replacing the current popup of the activity.
+    /// Without an implementation popups are not supported and the widget is dropped
+    fn open_popup(
+        &mut self,
+        _activity_id: &ActivityIdentifier,
+        _widget: SabiWidget,
+        _anchor: PopupAnchor,
+    ) {
+    }
+    fn close_popup(&mut self, _activity_id: &ActivityIdentifier) {}
+
+    /// Move an activity to another window or monitor, return false if the target doesn't exist
+    fn move_activity(
+        &mut self,
+        _activity_id: &ActivityIdentifier,
+        _target: OutputSelector,
+    ) -> bool {
+        false
+    }
+
+    /// Show or clear the urgency style of an activity, like an `urgent` css class
+    fn set_urgency(&mut self, _activity_id: &ActivityIdentifier, _urgent: bool) {}
+
+    /// Replace the widget of an activity keeping its position and animations, the old widget must be dropped.
+    ///
+    /// Without an implementation this returns an error, removing and adding the activity again
+    /// would reset its placement
+    fn replace_activity_widget(
+        &mut self,
+        _activity_id: &ActivityIdentifier,
+        _widget: SabiWidget,
+    ) -> RResult<(), RBoxError> {
+        RResult::RErr(RBoxError::new(NotImplementedError::default()))
+    }
+
+    /// Change the identifier of an activity in place, keeping its widget and position.
+    ///
+    /// Without an implementation this returns an error and the activity keeps the old identifier
+    fn rename_activity(
+        &mut self,
+        _old_id: &ActivityIdentifier,
+        _new_id: &ActivityIdentifier,
+    ) -> RResult<(), RBoxError> {
+        RResult::RErr(RBoxError::new(NotImplementedError::default()))
+    }
+
+    /// Render the container of an activity to a png image
+    fn capture_activity(
+        &self,
+        _activity_id: &ActivityIdentifier,
+    ) -> RResult<EncodedImage, RBoxError> {
+        RResult::RErr(RBoxError::new(NotImplementedError::default()))
+    }
+
+    /// Replace the css classes the module set on the container of an activity,
+    /// the LayoutManager must remember them to remove them on the next call
+    fn set_activity_css_classes(
+        &mut self,
+        _activity_id: &ActivityIdentifier,
+        _classes: RVec<RString>,
+    ) {
+    }
+
+    /// Show the progress of an operation on the frame of an activity, see `UIServerCommand::ReportProgress`
+    fn report_progress(
+        &mut self,
+        _activity_id: &ActivityIdentifier,
+        _token: u64,
+        _fraction: f64,
+        _label: ROption<RString>,
+    ) {
+    }
+
+    /// Called by the host after `init` with the sender for the events of the activities,
+    /// like pointer events and mode transitions.
+    ///
+    /// The host forwards every event to the module of its activity with `SabiModule::handle_event`
+    fn set_event_sender(&mut self, _sender: RSender<ModuleServerEvent>) {}
+
+    /// Install or replace a drop target on the container of an activity accepting `mime_types`,
+    /// the events are sent with the sender from `set_event_sender`. An empty list removes it
+    fn set_drop_target(&mut self, _activity_id: &ActivityIdentifier, _mime_types: RVec<RString>) {}
 }
 
 #[repr(C)]
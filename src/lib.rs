@@ -1,22 +1,62 @@
 use std::fmt::Display;
 
 use abi_stable::StableAbi;
+#[cfg(feature = "gtk")]
 use glib::translate::{FromGlibPtrNone, ToGlibPtr};
+#[cfg(feature = "gtk")]
 use gtk::{Application, Widget};
 
 pub mod activity_identifier;
+pub mod activity_mode;
+pub mod backoff;
+pub mod brightness;
+pub mod compact;
+pub mod compat;
+pub mod condition;
+pub mod config;
+pub mod discovery;
+pub mod events;
+pub mod feedback;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+pub mod geometry;
+pub mod history;
+pub mod host;
+pub mod integrity;
 pub mod layout;
+pub mod localization;
+pub mod logger;
+pub mod main_context;
+pub mod metadata;
 pub mod module;
+pub mod notification;
+pub mod panic;
+pub mod scheduling;
+pub mod schema;
+pub mod sound;
+#[cfg(feature = "static-modules")]
+pub mod static_module;
+pub mod surface;
+pub mod theme;
+pub mod version;
+pub mod web_content;
 
 pub extern crate abi_stable;
+#[cfg(feature = "gtk")]
 pub extern crate gdk;
+#[cfg(feature = "gtk")]
 pub extern crate glib;
+#[cfg(feature = "gtk")]
 pub extern crate glib_macros;
+#[cfg(feature = "gtk")]
 pub extern crate gtk;
+#[cfg(feature = "gtk")]
 pub extern crate gtk_layer_shell;
 pub extern crate log;
 
 /// gtk::Widget wrapper for sending trough the abi
+///
+/// Without the `gtk` feature it can only be passed around, not converted from or to a widget
 #[repr(C)]
 #[derive(StableAbi)]
 pub struct SabiWidget {
@@ -28,6 +68,7 @@ pub struct SabiWidget {
 // this can be send, because gtk::Widget can be processed only in the UI thread
 unsafe impl Send for SabiWidget {}
 
+#[cfg(feature = "gtk")]
 impl From<Widget> for SabiWidget {
     fn from(widget: Widget) -> Self {
         let widget_ptr: *mut gtk::ffi::GtkWidget = widget.to_glib_none().0;
@@ -37,6 +78,7 @@ impl From<Widget> for SabiWidget {
     }
 }
 
+#[cfg(feature = "gtk")]
 impl TryInto<Widget> for SabiWidget {
     type Error = String;
     fn try_into(self) -> Result<Widget, Self::Error> {
@@ -56,6 +98,8 @@ impl TryInto<Widget> for SabiWidget {
 }
 
 /// gtk::Application wrapper for sending trough the abi
+///
+/// Without the `gtk` feature it can only be passed around, not converted from or to an application
 #[repr(C)]
 #[derive(StableAbi)]
 pub struct SabiApplication {
@@ -68,6 +112,7 @@ pub struct SabiApplication {
 //TODO check if send is necessary
 // unsafe impl Send for SabiApplication {}
 
+#[cfg(feature = "gtk")]
 impl From<Application> for SabiApplication {
     fn from(app: Application) -> Self {
         let application_ptr: *mut gtk::ffi::GtkApplication = app.to_glib_none().0;
@@ -77,6 +122,7 @@ impl From<Application> for SabiApplication {
     }
 }
 
+#[cfg(feature = "gtk")]
 impl TryInto<Application> for SabiApplication {
     type Error = String;
     fn try_into(self) -> Result<Application, Self::Error> {
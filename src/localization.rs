@@ -0,0 +1,84 @@
+use abi_stable::{
+    std_types::{RHashMap, RString},
+    StableAbi,
+};
+
+/// A string with optional translations, keyed by locale (`it`, `pt_BR`, ...)
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocalizedString {
+    /// Used when there is no translation for the requested locale
+    pub default: RString,
+
+    #[sabi(last_prefix_field)]
+    pub translations: RHashMap<RString, RString>,
+}
+
+impl LocalizedString {
+    pub fn new(default: &str) -> Self {
+        Self {
+            default: default.into(),
+            translations: RHashMap::new(),
+        }
+    }
+    pub fn with_translation(mut self, locale: &str, value: &str) -> Self {
+        self.translations.insert(locale.into(), value.into());
+        self
+    }
+    /// Get the string for `locale`.
+    ///
+    /// The encoding and modifier (`.UTF-8`, `@euro`) are ignored, then the full locale is tried,
+    /// then only the language (`pt_BR` -> `pt`), and finally the default string is returned
+    pub fn resolve(&self, locale: &str) -> &str {
+        let locale = locale.split(['.', '@']).next().unwrap_or_default();
+        if let Some(value) = self.translations.get(locale) {
+            return value;
+        }
+        if let Some((language, _)) = locale.split_once('_') {
+            if let Some(value) = self.translations.get(language) {
+                return value;
+            }
+        }
+        &self.default
+    }
+    /// Get the string for the locale of the current process,
+    /// taken from `LC_ALL`, `LC_MESSAGES` or `LANG`
+    pub fn resolve_current(&self) -> &str {
+        match current_locale() {
+            Some(locale) => self.resolve(&locale),
+            None => &self.default,
+        }
+    }
+}
+
+impl From<&str> for LocalizedString {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+/// The locale used for messages by the current process, if set
+pub fn current_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|locale| !locale.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_localized_string_resolve() {
+        let name = LocalizedString::new("Music")
+            .with_translation("it", "Musica")
+            .with_translation("pt_BR", "Música");
+        assert_eq!(name.resolve("it_IT.UTF-8"), "Musica");
+        assert_eq!(name.resolve("pt_BR"), "Música");
+        assert_eq!(name.resolve("pt_PT"), "Music");
+        assert_eq!(name.resolve("C"), "Music");
+        assert_eq!(name.resolve(""), "Music");
+    }
+}
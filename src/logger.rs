@@ -0,0 +1,302 @@
+use abi_stable::{
+    external_types::crossbeam_channel::RSender,
+    sabi_trait,
+    sabi_trait::TD_Opaque,
+    std_types::{RArc, ROption, RStr, RString},
+    StableAbi,
+};
+
+use crate::module::UIServerCommand;
+
+pub type SabiLogger = SabiLogSink_TO<'static, RArc<()>>;
+
+/// Level of a log record, with the same meaning as `log::Level`
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogLevel {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<log::Level> for LogLevel {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => LogLevel::Error,
+            log::Level::Warn => LogLevel::Warn,
+            log::Level::Info => LogLevel::Info,
+            log::Level::Debug => LogLevel::Debug,
+            log::Level::Trace => LogLevel::Trace,
+        }
+    }
+}
+
+impl From<LogLevel> for log::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => log::Level::Error,
+            LogLevel::Warn => log::Level::Warn,
+            LogLevel::Info => log::Level::Info,
+            LogLevel::Debug => log::Level::Debug,
+            LogLevel::Trace => log::Level::Trace,
+        }
+    }
+}
+
+/// A log record sent from a module to the host
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    pub target: RString,
+    pub message: RString,
+    pub module_path: ROption<RString>,
+    pub file: ROption<RString>,
+
+    #[sabi(last_prefix_field)]
+    pub line: ROption<u32>,
+}
+
+/// Log sink implemented by the host, given to the modules with `SabiModule::set_logger`
+#[sabi_trait]
+pub trait SabiLogSink: Send + Sync {
+    /// Most verbose level accepted by the host, `RNone` if logging is disabled
+    fn max_level(&self) -> ROption<LogLevel>;
+    fn enabled(&self, level: LogLevel, target: RStr<'_>) -> bool;
+    fn log(&self, record: &LogRecord);
+
+    #[sabi(last_prefix_field)]
+    fn flush(&self);
+}
+
+/// Create a [`SabiLogger`] that forwards records to the logger of the current library,
+/// used by the host to share its logging configuration (levels, files, journald) with the modules
+pub fn host_logger() -> SabiLogger {
+    SabiLogSink_TO::from_ptr(RArc::new(HostLogSink), TD_Opaque)
+}
+
+/// Install `logger` as the logger of the current library (the module),
+/// so that the `log` macros used by the module are handled by the host.
+///
+/// Fails if a logger was already installed
+pub fn init_logger(logger: SabiLogger) -> Result<(), log::SetLoggerError> {
+    let max_level = match logger.max_level() {
+        ROption::RSome(level) => log::Level::from(level).to_level_filter(),
+        ROption::RNone => log::LevelFilter::Off,
+    };
+    log::set_boxed_logger(Box::new(ForwardingLogger { sink: logger }))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+/// Create a [`SabiLogger`] that sends the records up to `max_level` to the host with `UIServerCommand::Log`,
+/// for modules that don't receive a logger from the host
+pub fn command_logger(
+    app_send: RSender<UIServerCommand>,
+    module_name: &str,
+    max_level: LogLevel,
+) -> SabiLogger {
+    SabiLogSink_TO::from_ptr(
+        RArc::new(CommandLogSink {
+            app_send,
+            module_name: module_name.into(),
+            max_level,
+        }),
+        TD_Opaque,
+    )
+}
+
+struct HostLogSink;
+
+impl SabiLogSink for HostLogSink {
+    fn max_level(&self) -> ROption<LogLevel> {
+        log::max_level().to_level().map(LogLevel::from).into()
+    }
+    fn enabled(&self, level: LogLevel, target: RStr<'_>) -> bool {
+        let metadata = log::Metadata::builder()
+            .level(level.into())
+            .target(target.as_str())
+            .build();
+        log::logger().enabled(&metadata)
+    }
+    fn log(&self, record: &LogRecord) {
+        log::logger().log(
+            &log::Record::builder()
+                .level(record.level.into())
+                .target(&record.target)
+                .module_path(record.module_path.as_ref().map(|path| path.as_str()).into())
+                .file(record.file.as_ref().map(|file| file.as_str()).into())
+                .line(record.line.into())
+                .args(format_args!("{}", record.message))
+                .build(),
+        );
+    }
+    fn flush(&self) {
+        log::logger().flush();
+    }
+}
+
+struct CommandLogSink {
+    app_send: RSender<UIServerCommand>,
+    module_name: RString,
+    max_level: LogLevel,
+}
+
+impl SabiLogSink for CommandLogSink {
+    fn max_level(&self) -> ROption<LogLevel> {
+        ROption::RSome(self.max_level)
+    }
+    fn enabled(&self, level: LogLevel, _target: RStr<'_>) -> bool {
+        level <= self.max_level
+    }
+    fn log(&self, record: &LogRecord) {
+        let _ = self.app_send.send(UIServerCommand::Log {
+            module_name: self.module_name.clone(),
+            level: record.level,
+            message: record.message.clone(),
+        });
+    }
+    fn flush(&self) {}
+}
+
+struct ForwardingLogger {
+    sink: SabiLogger,
+}
+
+impl log::Log for ForwardingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.sink
+            .enabled(metadata.level().into(), metadata.target().into())
+    }
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        self.sink.log(&LogRecord {
+            level: record.level().into(),
+            target: record.target().into(),
+            message: record.args().to_string().into(),
+            module_path: record.module_path().map(RString::from).into(),
+            file: record.file().map(RString::from).into(),
+            line: record.line().into(),
+        });
+    }
+    fn flush(&self) {
+        self.sink.flush();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use log::Log;
+
+    use super::*;
+
+    struct TestSink {
+        max_level: LogLevel,
+        records: Arc<Mutex<Vec<LogRecord>>>,
+    }
+
+    impl SabiLogSink for TestSink {
+        fn max_level(&self) -> ROption<LogLevel> {
+            ROption::RSome(self.max_level)
+        }
+        fn enabled(&self, level: LogLevel, _target: RStr<'_>) -> bool {
+            level <= self.max_level
+        }
+        fn log(&self, record: &LogRecord) {
+            self.records.lock().unwrap().push(record.clone());
+        }
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn test_log_level_conversion() {
+        for level in [
+            log::Level::Error,
+            log::Level::Warn,
+            log::Level::Info,
+            log::Level::Debug,
+            log::Level::Trace,
+        ] {
+            assert_eq!(log::Level::from(LogLevel::from(level)), level);
+        }
+        assert!(LogLevel::Error < LogLevel::Trace);
+    }
+
+    #[test]
+    fn test_forwarding_logger() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let logger = ForwardingLogger {
+            sink: SabiLogSink_TO::from_ptr(
+                RArc::new(TestSink {
+                    max_level: LogLevel::Info,
+                    records: records.clone(),
+                }),
+                TD_Opaque,
+            ),
+        };
+        logger.log(
+            &log::Record::builder()
+                .level(log::Level::Warn)
+                .target("music")
+                .line(Some(12))
+                .args(format_args!("player {} not found", "spotify"))
+                .build(),
+        );
+        logger.log(
+            &log::Record::builder()
+                .level(log::Level::Debug)
+                .target("music")
+                .args(format_args!("filtered"))
+                .build(),
+        );
+
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].level, LogLevel::Warn);
+        assert_eq!(records[0].target, "music");
+        assert_eq!(records[0].message, "player spotify not found");
+        assert_eq!(records[0].line, ROption::RSome(12));
+    }
+
+    #[test]
+    fn test_command_logger() {
+        let (app_send, app_recv) = abi_stable::external_types::crossbeam_channel::unbounded();
+        let logger = ForwardingLogger {
+            sink: command_logger(app_send, "music", LogLevel::Info),
+        };
+        assert_eq!(logger.sink.max_level(), ROption::RSome(LogLevel::Info));
+        logger.log(
+            &log::Record::builder()
+                .level(log::Level::Error)
+                .args(format_args!("connection lost"))
+                .build(),
+        );
+        logger.log(
+            &log::Record::builder()
+                .level(log::Level::Trace)
+                .args(format_args!("filtered"))
+                .build(),
+        );
+
+        let commands: Vec<_> = std::iter::from_fn(|| app_recv.try_recv().ok()).collect();
+        assert_eq!(commands.len(), 1);
+        let UIServerCommand::Log {
+            module_name,
+            level,
+            message,
+        } = &commands[0]
+        else {
+            panic!("unexpected command {}", commands[0].name());
+        };
+        assert_eq!(module_name, "music");
+        assert_eq!(*level, LogLevel::Error);
+        assert_eq!(message, "connection lost");
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt::Display;
+
+use abi_stable::{
+    external_types::crossbeam_channel::RSender,
+    sabi_trait,
+    sabi_trait::TD_Opaque,
+    std_types::{RBox, RBoxError, RResult},
+};
+
+use crate::module::UIServerCommand;
+
+/// A closure that can be sent trough the abi, see [`MainTask`]
+#[sabi_trait]
+pub trait MainTaskFn: Send {
+    /// Run the closure, the calls after the first one do nothing
+    #[sabi(last_prefix_field)]
+    fn run(&mut self);
+}
+
+/// FFI-safe boxed closure, run by the host on its main context after a `UIServerCommand::RunOnMain`
+pub type MainTask = MainTaskFn_TO<'static, RBox<()>>;
+
+struct ClosureTask<F>(Option<F>);
+
+impl<F: FnOnce() + Send> MainTaskFn for ClosureTask<F> {
+    fn run(&mut self) {
+        if let Some(f) = self.0.take() {
+            // a panic can't unwind into the host, the result sender is dropped instead
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+        }
+    }
+}
+
+/// Box `f` in a [`MainTask`]
+pub fn main_task(f: impl FnOnce() + Send + 'static) -> MainTask {
+    MainTaskFn_TO::from_value(ClosureTask(Some(f)), TD_Opaque)
+}
+
+/// Returns `true` if the current thread owns the default main context,
+/// which is always the case for the gtk thread while the main loop is running
+#[cfg(feature = "gtk")]
+pub fn is_main_context() -> bool {
+    glib::MainContext::default().is_owner()
+}
+
+/// Panic if the current thread isn't running the default main context,
+/// use it at the start of functions that call the gtk api
+#[cfg(feature = "gtk")]
+#[track_caller]
+pub fn assert_main_context() {
+    assert!(
+        is_main_context(),
+        "this function must be called from the gtk main context, use `run_on_main` or `glib::MainContext::default().spawn_local()`"
+    );
+}
+
+/// Run `f` on the main context of the host (the gtk thread) and wait for its result.
+///
+/// If the current thread already owns the main context `f` is run immediately,
+/// otherwise it's sent to the host with `UIServerCommand::RunOnMain`, so it can be used from any producer thread.
+///
+/// This blocks the current thread, don't call it from a thread the main context is waiting for
+#[cfg(feature = "gtk")]
+pub fn run_on_main<T, F>(app_send: &RSender<UIServerCommand>, f: F) -> RResult<T, RBoxError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    if is_main_context() {
+        return RResult::ROk(f());
+    }
+    let (tx, rx) = std::sync::mpsc::sync_channel(1);
+    if let RResult::RErr(err) = spawn_on_main(app_send, move || {
+        let _ = tx.send(f());
+    }) {
+        return RResult::RErr(err);
+    }
+    match rx.recv() {
+        Ok(value) => RResult::ROk(value),
+        Err(_) => RResult::RErr(RBoxError::new(MainContextError {})),
+    }
+}
+
+/// Send `f` to the main context of the host without waiting for it
+pub fn spawn_on_main<F>(app_send: &RSender<UIServerCommand>, f: F) -> RResult<(), RBoxError>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let command = UIServerCommand::RunOnMain { task: main_task(f) };
+    match app_send.send(command) {
+        Ok(()) => RResult::ROk(()),
+        Err(_) => RResult::RErr(RBoxError::new(MainContextError {})),
+    }
+}
+
+/// Error returned by [`run_on_main`] and [`spawn_on_main`] when the task is dropped without running,
+/// usually because the host stopped or the task panicked
+#[derive(Debug, Default)]
+pub struct MainContextError {}
+
+impl Display for MainContextError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the main context dropped the task before running it")
+    }
+}
+impl std::error::Error for MainContextError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_main_task_runs_once() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut task = main_task(move || tx.send(()).unwrap());
+        task.run();
+        task.run();
+        assert_eq!(rx.try_iter().count(), 1);
+    }
+}
@@ -0,0 +1,358 @@
+//! Typed values of the additional metadata of an activity.
+//!
+//! The typed values are stored next to the string map of `ActivityMetadata`,
+//! so layout managers can read numbers and flags without parsing them out of strings.
+//!
+//! The `KEY_*` constants are the keys that layout managers commonly read,
+//! `ActivityMetadata` has typed accessors for them (like `ActivityMetadata::order_weight`).
+
+use std::fmt::Display;
+
+use abi_stable::{
+    std_types::{ROption, RString, RVec},
+    StableAbi,
+};
+
+use crate::{geometry::SizeAbi, surface::OutputSelector};
+
+/// Ordering weight of the activity (int), higher weights come first
+pub const KEY_ORDER_WEIGHT: &str = "order-weight";
+/// Name of an icon from the icon theme representing the activity (string)
+pub const KEY_ICON_NAME: &str = "icon-name";
+/// The activity should be the only one shown in its window while it's expanded (bool)
+pub const KEY_EXCLUSIVE: &str = "exclusive";
+
+/// Prefix of the additional metadata keys that tells who writes them, like `layout.expanded`,
+/// so the entries of modules, layout managers and the host don't collide.
+///
+/// The keys without a known prefix (like the `KEY_*` constants) are shared
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MetadataNamespace {
+    /// Written by the LayoutManager
+    Layout,
+    /// Written by the module of the activity
+    Module,
+    /// Written by the user, with the config or the cli
+    User,
+    /// Written by the host
+    Host,
+}
+
+impl MetadataNamespace {
+    pub const ALL: [MetadataNamespace; 4] = [
+        MetadataNamespace::Layout,
+        MetadataNamespace::Module,
+        MetadataNamespace::User,
+        MetadataNamespace::Host,
+    ];
+
+    pub fn prefix(self) -> &'static str {
+        match self {
+            MetadataNamespace::Layout => "layout.",
+            MetadataNamespace::Module => "module.",
+            MetadataNamespace::User => "user.",
+            MetadataNamespace::Host => "host.",
+        }
+    }
+    /// The full key of `name` in this namespace, like `layout.expanded`
+    pub fn key(self, name: &str) -> String {
+        format!("{}{name}", self.prefix())
+    }
+    /// Namespace and name of `key`, `None` if it has no known prefix
+    pub fn split(key: &str) -> Option<(MetadataNamespace, &str)> {
+        Self::ALL
+            .into_iter()
+            .find_map(|namespace| Some((namespace, key.strip_prefix(namespace.prefix())?)))
+    }
+}
+
+/// Size hints of an activity in logical pixels, so the LayoutManager can allocate space
+/// before the widget reports its size. The hints set to `RNone` are unconstrained
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SizeHints {
+    pub min_width: ROption<i32>,
+    pub natural_width: ROption<i32>,
+    pub max_width: ROption<i32>,
+    pub min_height: ROption<i32>,
+    pub natural_height: ROption<i32>,
+
+    #[sabi(last_prefix_field)]
+    pub max_height: ROption<i32>,
+}
+
+impl SizeHints {
+    /// `size` clamped between the minimum and the maximum, the minimum wins if they conflict
+    pub fn constrain(&self, size: SizeAbi) -> SizeAbi {
+        let clamp = |value: i32, min: ROption<i32>, max: ROption<i32>| {
+            let value = max.into_option().map_or(value, |max| value.min(max));
+            min.into_option().map_or(value, |min| value.max(min))
+        };
+        SizeAbi {
+            width: clamp(size.width, self.min_width, self.max_width),
+            height: clamp(size.height, self.min_height, self.max_height),
+        }
+    }
+    /// The natural size if both dimensions are set, constrained by the minimum and the maximum
+    pub fn natural_size(&self) -> Option<SizeAbi> {
+        let size = SizeAbi {
+            width: self.natural_width.into_option()?,
+            height: self.natural_height.into_option()?,
+        };
+        Some(self.constrain(size))
+    }
+}
+
+/// Edge of the output an activity prefers to be shown at
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlacementEdge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Position of an activity along its edge, `Start` is the left (or top) end
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlacementAlign {
+    Start,
+    Center,
+    End,
+}
+
+/// Where an activity would like to be shown, like "on the right side of the primary monitor".
+///
+/// These are hints: the LayoutManager decides, the hints set to `RNone` are left to it
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlacementHint {
+    pub edge: ROption<PlacementEdge>,
+    pub align: ROption<PlacementAlign>,
+
+    #[sabi(last_prefix_field)]
+    pub output: ROption<OutputSelector>,
+}
+
+impl PlacementHint {
+    pub fn edge(mut self, edge: PlacementEdge) -> Self {
+        self.edge = ROption::RSome(edge);
+        self
+    }
+    pub fn align(mut self, align: PlacementAlign) -> Self {
+        self.align = ROption::RSome(align);
+        self
+    }
+    pub fn output(mut self, output: OutputSelector) -> Self {
+        self.output = ROption::RSome(output);
+        self
+    }
+}
+
+/// A typed metadata value, see `ActivityMetadata::set_value`
+///
+/// Floats are compared by their bits, so a value is always equal to itself, even if it's NaN
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MetadataValue {
+    String(RString),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    StringList(RVec<RString>),
+}
+
+impl MetadataValue {
+    /// Name of the type of the value, for error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            MetadataValue::String(_) => "string",
+            MetadataValue::Int(_) => "int",
+            MetadataValue::Float(_) => "float",
+            MetadataValue::Bool(_) => "bool",
+            MetadataValue::StringList(_) => "string list",
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MetadataValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            MetadataValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+    /// The value as a float, integers are converted
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            MetadataValue::Float(value) => Some(*value),
+            MetadataValue::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            MetadataValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+    pub fn as_string_list(&self) -> Option<&[RString]> {
+        match self {
+            MetadataValue::StringList(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for MetadataValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (MetadataValue::String(a), MetadataValue::String(b)) => a == b,
+            (MetadataValue::Int(a), MetadataValue::Int(b)) => a == b,
+            (MetadataValue::Float(a), MetadataValue::Float(b)) => a.to_bits() == b.to_bits(),
+            (MetadataValue::Bool(a), MetadataValue::Bool(b)) => a == b,
+            (MetadataValue::StringList(a), MetadataValue::StringList(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+impl Eq for MetadataValue {}
+
+/// The string form used by [`Condition`](crate::condition::Condition), lists are joined with `,`
+impl Display for MetadataValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetadataValue::String(value) => write!(f, "{value}"),
+            MetadataValue::Int(value) => write!(f, "{value}"),
+            MetadataValue::Float(value) => write!(f, "{value}"),
+            MetadataValue::Bool(value) => write!(f, "{value}"),
+            MetadataValue::StringList(values) => write!(f, "{}", values.join(",")),
+        }
+    }
+}
+
+impl From<&str> for MetadataValue {
+    fn from(value: &str) -> Self {
+        MetadataValue::String(value.into())
+    }
+}
+impl From<i64> for MetadataValue {
+    fn from(value: i64) -> Self {
+        MetadataValue::Int(value)
+    }
+}
+impl From<f64> for MetadataValue {
+    fn from(value: f64) -> Self {
+        MetadataValue::Float(value)
+    }
+}
+impl From<bool> for MetadataValue {
+    fn from(value: bool) -> Self {
+        MetadataValue::Bool(value)
+    }
+}
+impl From<Vec<String>> for MetadataValue {
+    fn from(value: Vec<String>) -> Self {
+        MetadataValue::StringList(value.into_iter().map(RString::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::module::ActivityMetadata;
+
+    #[test]
+    fn test_namespaced_metadata() {
+        assert_eq!(
+            MetadataNamespace::split("layout.expanded"),
+            Some((MetadataNamespace::Layout, "expanded"))
+        );
+        assert_eq!(MetadataNamespace::split("layoutexpanded"), None);
+
+        let mut metadata = ActivityMetadata::new();
+        metadata.set_namespaced_metadata(MetadataNamespace::Module, "state", "playing");
+        metadata.set_namespaced_metadata(MetadataNamespace::Layout, "state", "expanded");
+        metadata.set_additional_metadata("state".into(), "shared".into());
+        assert_eq!(
+            metadata.namespaced_metadata(MetadataNamespace::Module, "state"),
+            Some("playing".to_string())
+        );
+        assert_eq!(
+            metadata.namespace_entries(MetadataNamespace::Layout),
+            vec![("state".to_string(), "expanded".to_string())]
+        );
+        metadata.remove_namespaced_metadata(MetadataNamespace::Layout, "state");
+        assert!(metadata
+            .namespace_entries(MetadataNamespace::Layout)
+            .is_empty());
+        assert_eq!(
+            metadata.additional_metadata("state"),
+            Some("shared".to_string())
+        );
+    }
+
+    #[test]
+    fn test_size_hints() {
+        let hints = SizeHints {
+            min_width: ROption::RSome(100),
+            max_width: ROption::RSome(80),
+            max_height: ROption::RSome(40),
+            natural_width: ROption::RSome(300),
+            natural_height: ROption::RSome(60),
+            ..Default::default()
+        };
+        assert_eq!(
+            hints.constrain(SizeAbi {
+                width: 10,
+                height: 10
+            }),
+            SizeAbi {
+                width: 100,
+                height: 10
+            }
+        );
+        assert_eq!(
+            hints.natural_size(),
+            Some(SizeAbi {
+                width: 100,
+                height: 40
+            })
+        );
+        let mut metadata = ActivityMetadata::new();
+        assert_eq!(metadata.size_hints(), None);
+        metadata.set_size_hints(hints);
+        assert_eq!(metadata.size_hints(), Some(&hints));
+
+        metadata.set_preferred_size(SizeAbi {
+            width: 90,
+            height: 30,
+        });
+        assert_eq!(
+            metadata.preferred_size(),
+            Some(SizeAbi {
+                width: 90,
+                height: 30
+            })
+        );
+        assert_eq!(
+            metadata.size_hints().unwrap().natural_width,
+            ROption::RSome(90)
+        );
+        assert_eq!(
+            metadata.size_hints().unwrap().min_width,
+            ROption::RSome(100)
+        );
+    }
+}
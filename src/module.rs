@@ -6,11 +6,36 @@ use abi_stable::{
     library::RootModule,
     package_version_strings, sabi_trait,
     sabi_types::VersionStrings,
-    std_types::{RBox, RBoxError, RHashMap, ROption, RResult, RStr, RString},
+    std_types::{RBox, RBoxError, RHashMap, ROption, RResult, RSlice, RStr, RString, RVec},
     StableAbi,
 };
 
-use crate::{NotImplementedError, SabiWidget};
+use crate::{
+    activity_mode::ActivityMode,
+    brightness::BrightnessRequest,
+    condition::Condition,
+    config::ConfigError,
+    events::{
+        AppEvent, CompositorEvent, ConnectivityState, EventMask, InteractionEvent, KeyEvent,
+        ModuleEvent, ModuleServerEvent, ShortcutScope,
+    },
+    feedback::CommandError,
+    history::CommandHistory,
+    host::HostContextRef,
+    localization::LocalizedString,
+    logger::{LogLevel, SabiLogger},
+    main_context::MainTask,
+    metadata::{MetadataValue, PlacementHint, SizeHints},
+    notification::NotificationContent,
+    scheduling::SchedulingHint,
+    sound::SoundSpec,
+    surface::{
+        CursorKind, EncodedImage, OutputSelector, OverlayLayer, PopupAnchor, Tooltip, WindowProps,
+    },
+    version::{check_min_version, VersionError},
+    web_content::WebContentPolicy,
+    NotImplementedError, SabiWidget,
+};
 
 pub type ModuleType = SabiModule_TO<'static, RBox<()>>;
 
@@ -40,6 +65,9 @@ pub trait SabiModule {
 
     /// Update the config struct from the section of the config file for this module
     ///
+    /// If the config is invalid, the returned [`ConfigError`] should contain the location of the error,
+    /// the key path is relative to the module section like in `validate_config`
+    ///
     /// # Examples
     /// ```
     /// #[derive(Serialize, Deserialize, Clone)]
@@ -54,7 +82,7 @@ pub trait SabiModule {
     ///     }
     /// }
     ///
-    /// fn update_config(&mut self, config: RString) -> RResult<(), RBoxError> {
+    /// fn update_config(&mut self, config: RString) -> RResult<(), ConfigError> {
     ///     let conf = ron::from_str::<ron::Value>(&config)
     ///         .with_context(|| "failed to parse config to value")
     ///         .unwrap();
@@ -69,7 +97,7 @@ pub trait SabiModule {
     ///     ROk(())
     /// }
     /// ```
-    fn update_config(&mut self, config: RString) -> RResult<(), RBoxError>;
+    fn update_config(&mut self, config: RString) -> RResult<(), ConfigError>;
 
     /// Restart the producers registered on the BaseModule
     ///
@@ -109,6 +137,122 @@ pub trait SabiModule {
     fn cli_command(&self, _command: RString) -> RResult<RString, RBoxError> {
         RResult::RErr(RBoxError::new(NotImplementedError::default()))
     }
+
+    /// Called by the host when the network connectivity changes.
+    ///
+    /// Modules that fetch data from the network can use this to refresh immediately
+    /// when the connection comes back, or to show an offline state, instead of polling
+    fn on_connectivity_changed(&self, _state: ConnectivityState) {}
+
+    /// Check the section of the config file for this module without applying it.
+    ///
+    /// The host calls this before `update_config` to print precise diagnostics,
+    /// the key path should be relative to the module section, the host adds the module name.
+    /// By default every config is accepted and errors are only reported by `update_config`
+    fn validate_config(&self, _config: RString) -> RResult<(), ConfigError> {
+        RResult::ROk(())
+    }
+
+    /// Called by the host for every event received from the compositor.
+    ///
+    /// The host keeps a single IPC connection, so modules don't need to open their own
+    fn on_compositor_event(&self, _event: CompositorEvent) {}
+
+    /// Called by the host when the user interacts with the container of one of the activities of this module.
+    ///
+    /// The LayoutManager may use some gestures itself (for example to change the activity mode),
+    /// it decides which ones are forwarded
+    fn on_activity_interaction(&self, _id: ActivityIdentifier, _action: InteractionEvent) {}
+
+    /// Called by the host right after the module is created, with the logger of the host.
+    ///
+    /// The default implementation installs it as the logger of the module with [`crate::logger::init_logger`],
+    /// so the log lines of the module follow the logging configuration of the host.
+    /// It does nothing if the module already installed its own logger
+    fn set_logger(&self, logger: SabiLogger) {
+        let _ = crate::logger::init_logger(logger);
+    }
+
+    /// Called by the host before `init` when the module is restarted after a failure,
+    /// only if the module has the [`ModuleFlags::COMMAND_HISTORY`] flag.
+    ///
+    /// `history` contains the last commands the previous instance sent,
+    /// so the module can reconcile its state (for example remove activities it doesn't register anymore)
+    fn restore_state(&mut self, _history: CommandHistory) {}
+
+    /// Called by the host when its state changes, see [`AppEvent`]
+    fn on_app_event(&self, _event: AppEvent) {}
+
+    /// Get the config currently used by the module in ron format, after merging the defaults.
+    ///
+    /// Used by `dynisland config export` to produce a [`ConfigSnapshot`](crate::config::ConfigSnapshot),
+    /// passing the result to `update_config` must produce the same config
+    fn export_effective_config(&self) -> RResult<RString, RBoxError> {
+        RResult::RErr(RBoxError::new(NotImplementedError::default()))
+    }
+
+    /// Called by the host for every key event while one of the activities of this module
+    /// holds an input grab, see `UIServerCommand::RequestInputGrab`.
+    ///
+    /// Return false if the event was not handled, the host will then process it as if there was no grab
+    fn on_key_event(&self, _id: ActivityIdentifier, _event: KeyEvent) -> bool {
+        false
+    }
+
+    /// Called by the host when another module sends a message with `UIServerCommand::SendToModule`.
+    ///
+    /// The format of `payload` is agreed between the modules,
+    /// the result is sent back to the sender if it asked for a reply
+    fn on_module_message(&self, _payload: RVec<u8>) -> ROption<RVec<u8>> {
+        ROption::RNone
+    }
+
+    /// Called by the host on the UI thread when a timer scheduled with `UIServerCommand::Schedule` fires
+    fn on_timer(&self, _token: u64) {}
+
+    /// Called by the host right after the module is created, with the sender the host uses to report
+    /// the commands of this module that failed (like `RemoveActivity` of an activity that doesn't exist).
+    ///
+    /// Without an implementation the errors are only logged by the host
+    fn set_error_sender(&self, _sender: RSender<CommandError>) {}
+
+    /// Called by the host before the module is dropped when it exits or restarts,
+    /// after the grace period of `AppEvent::HostShuttingDown`.
+    ///
+    /// The module should stop its producers and flush what it didn't save yet, the host doesn't wait for long
+    fn shutdown(&mut self) {}
+
+    /// Called by the host for every event sent to this module, hosts should use it instead of the single `on_*` methods.
+    ///
+    /// The default implementation calls the `on_*` method of the event, so modules can implement either.
+    /// The result is only meaningful for key and pointer events: false if the event was not handled
+    fn handle_event(&self, event: ModuleServerEvent) -> bool {
+        match event {
+            ModuleServerEvent::Compositor(event) => self.on_compositor_event(event),
+            ModuleServerEvent::Connectivity(state) => self.on_connectivity_changed(state),
+            ModuleServerEvent::App(event) => self.on_app_event(event),
+            ModuleServerEvent::Interaction { activity_id, event } => {
+                self.on_activity_interaction(activity_id, event)
+            }
+            ModuleServerEvent::Key { activity_id, event } => {
+                return self.on_key_event(activity_id, event)
+            }
+            ModuleServerEvent::Timer { token } => self.on_timer(token),
+            // only delivered with handle_event
+            ModuleServerEvent::Pointer { .. }
+            | ModuleServerEvent::ModeTransition { .. }
+            | ModuleServerEvent::VisibilityChanged { .. }
+            | ModuleServerEvent::Drop { .. }
+            | ModuleServerEvent::ShortcutPressed { .. }
+            | ModuleServerEvent::ConfigFileChanged { .. }
+            | ModuleServerEvent::Power(_)
+            | ModuleServerEvent::Idle(_)
+            | ModuleServerEvent::ThemeChanged(_)
+            | ModuleServerEvent::ActivityDismissedByUser { .. }
+            | ModuleServerEvent::SizeAllocated { .. } => return false,
+        }
+        true
+    }
 }
 
 #[repr(C)]
@@ -144,6 +288,255 @@ pub struct ModuleBuilder {
     /// The name of the module
     #[sabi(last_prefix_field)]
     pub name: RStr<'static>,
+
+    /// The version of the module, like `env!("CARGO_PKG_VERSION")`
+    ///
+    /// This is unrelated to the abi version, it's used by the host to show the module version,
+    /// check the minimum versions required in the config and migrate old configs.
+    /// It's empty for modules built with an older version of this crate
+    #[sabi(missing_field(default))]
+    pub version: RStr<'static>,
+
+    /// The oldest version of dynisland this module can run on, empty if there is no requirement
+    ///
+    /// Modules that use features added in newer versions should set this,
+    /// so that the host can refuse to load them with a clear error
+    /// instead of panicking when a missing field or method is accessed
+    #[sabi(missing_field(default))]
+    pub min_dynisland_version: RStr<'static>,
+
+    /// Short human readable description of the module, shown by `dynisland module list`
+    #[sabi(missing_field(default))]
+    pub description: RStr<'static>,
+
+    /// The author of the module, empty if not set
+    #[sabi(missing_field(default))]
+    pub author: RStr<'static>,
+
+    /// SPDX license identifier of the module, like `MIT`, empty if not set
+    #[sabi(missing_field(default))]
+    pub license: RStr<'static>,
+
+    /// Icon of the module, shown by settings UIs and module pickers
+    #[sabi(missing_field(default))]
+    pub icon: ROption<ModuleIcon>,
+
+    /// Capabilities of the module, used by the host to decide how to instantiate it before calling `new`
+    #[sabi(missing_field(default))]
+    pub flags: ModuleFlags,
+
+    /// Create a new instance of a module already configured with `config`,
+    /// the section of the config file for this module.
+    ///
+    /// This avoids showing the default content before `update_config` is called,
+    /// if it's `RNone` the host uses `new` followed by `update_config`
+    #[sabi(missing_field(default))]
+    pub new_with_config: ROption<
+        extern "C" fn(
+            app_send: RSender<UIServerCommand>,
+            config: RString,
+        ) -> RResult<ModuleType, RBoxError>,
+    >,
+
+    /// Create a new instance of a module with access to the [`HostContext`](crate::host::HostContext)
+    ///
+    /// If it's `RSome` the host uses it instead of `new`, the config is then applied with `update_config`
+    #[sabi(missing_field(default))]
+    pub new_with_context: ROption<
+        extern "C" fn(
+            app_send: RSender<UIServerCommand>,
+            ctx: HostContextRef,
+        ) -> RResult<ModuleType, RBoxError>,
+    >,
+
+    /// Priority preference of the module, applied by the host to the threads registered with
+    /// `UIServerCommand::RegisterProducerThread` so heavy modules don't starve the UI thread
+    #[sabi(missing_field(default))]
+    pub scheduling: SchedulingHint,
+
+    /// Activities the module will register, known before the module is created.
+    ///
+    /// The LayoutManager can reserve their slots and show placeholders before `init` runs,
+    /// with [`ModuleFlags::LAZY_INIT`] the host can also wait until one of them is visible to create the module
+    #[sabi(missing_field(default))]
+    pub activities: RSlice<'static, ActivityDeclaration>,
+
+    /// Name of the section of the config file for this module, if it's `RNone` the module name is used.
+    ///
+    /// This allows renaming a module or shipping multiple variants of it without breaking existing configs
+    #[sabi(missing_field(default))]
+    pub config_key: ROption<RStr<'static>>,
+
+    /// Choose the optional features to use among the ones supported by the host.
+    ///
+    /// The host calls this before creating the module with the names of the features it supports
+    /// (like [`FEATURE_EVENT_BUS`](crate::host::FEATURE_EVENT_BUS)), the module returns the ones it will use.
+    /// If it's `RNone` the module doesn't use any optional feature
+    #[sabi(missing_field(default))]
+    pub negotiate: ROption<extern "C" fn(host_features: RVec<RString>) -> RVec<RString>>,
+
+    /// Initialization priority, modules with a higher priority are initialized first, the default is 0.
+    ///
+    /// Modules that other modules depend on (like shared MPRIS or compositor IPC providers) should use a positive value,
+    /// the LayoutManager can also use it as the default ordering of the activities.
+    /// See [`sort_by_priority`]
+    #[sabi(missing_field(default))]
+    pub priority: i32,
+
+    /// Destroy an instance of the module inside the library that created it.
+    ///
+    /// The host must call it (with [`ModuleBuilderRef::drop_instance`]) before unloading the library,
+    /// so the memory and GTK objects of the module are freed by the right allocator
+    #[sabi(missing_field(default))]
+    pub drop_module: ROption<extern "C" fn(module: ModuleType)>,
+
+    /// Hex encoded SHA-256 of the build of the module, see [`crate::integrity`]
+    #[sabi(missing_field(default))]
+    pub checksum: ROption<RStr<'static>>,
+
+    /// Signature of [`signed_message`](crate::integrity::signed_message) with the name, version and checksum of the module
+    #[sabi(missing_field(default))]
+    pub signature: ROption<RSlice<'static, u8>>,
+
+    /// Get the default config of the module in ron format without creating an instance,
+    /// like `SabiModule::default_config`.
+    ///
+    /// Used by the host to generate the default config file for all the installed modules
+    #[sabi(missing_field(default))]
+    pub default_config: ROption<extern "C" fn() -> RResult<RString, RBoxError>>,
+}
+
+impl ModuleBuilder {
+    /// Builder with only the required fields, all the optional fields have their default value
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let builder = ModuleBuilder {
+    ///     flags: ModuleFlags::MULTI_INSTANCE,
+    ///     ..ModuleBuilder::with_defaults(new, NAME)
+    /// };
+    /// ```
+    pub const fn with_defaults(
+        new: extern "C" fn(app_send: RSender<UIServerCommand>) -> RResult<ModuleType, RBoxError>,
+        name: &'static str,
+    ) -> Self {
+        Self {
+            new,
+            name: RStr::from_str(name),
+            version: RStr::from_str(""),
+            min_dynisland_version: RStr::from_str(""),
+            description: RStr::from_str(""),
+            author: RStr::from_str(""),
+            license: RStr::from_str(""),
+            icon: ROption::RNone,
+            flags: ModuleFlags::NONE,
+            new_with_config: ROption::RNone,
+            new_with_context: ROption::RNone,
+            scheduling: SchedulingHint::Normal,
+            activities: RSlice::from_slice(&[]),
+            config_key: ROption::RNone,
+            negotiate: ROption::RNone,
+            priority: 0,
+            drop_module: ROption::RNone,
+            checksum: ROption::RNone,
+            signature: ROption::RNone,
+            default_config: ROption::RNone,
+        }
+    }
+}
+
+/// Sort the modules in initialization order, from the highest priority,
+/// modules with the same priority keep their order
+pub fn sort_by_priority(modules: &mut [ModuleBuilderRef]) {
+    modules.sort_by_key(|module| std::cmp::Reverse(module.priority()));
+}
+
+/// Static description of an activity of a module, see `ModuleBuilder::activities`
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActivityDeclaration {
+    /// Activity name, the same that will be used in the `ActivityIdentifier`
+    pub name: RStr<'static>,
+    /// Name of the window the activity should be shown in
+    pub window_name: ROption<RStr<'static>>,
+
+    /// Name shown in the placeholder, the activity name is used if it's empty
+    #[sabi(last_prefix_field)]
+    pub display_name: RStr<'static>,
+}
+
+impl ActivityDeclaration {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name: RStr::from_str(name),
+            window_name: ROption::RNone,
+            display_name: RStr::from_str(""),
+        }
+    }
+    /// Identifier of the declared activity, the metadata contains only the window name
+    pub fn identifier(&self, module_name: &str) -> ActivityIdentifier {
+        let mut id = ActivityIdentifier::new(module_name, self.name.as_str());
+        if let ROption::RSome(window_name) = self.window_name {
+            id.metadata_mut().set_window_name(window_name.as_str());
+        }
+        id
+    }
+}
+
+/// Capabilities of a module, combined with `|`
+///
+/// Unknown bits must be ignored, so that new flags can be added without breaking older hosts
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ModuleFlags(u32);
+
+impl ModuleFlags {
+    pub const NONE: Self = Self(0);
+    /// `new` and `init` must be called from the gtk main context
+    pub const NEEDS_GTK_MAIN_CONTEXT: Self = Self(1);
+    /// The module can be instantiated more than once, each with its own config section
+    pub const MULTI_INSTANCE: Self = Self(1 << 1);
+    /// The module can work without registering any activity, for example with only cli commands
+    pub const HEADLESS_CAPABLE: Self = Self(1 << 2);
+    /// The host should keep a [`CommandHistory`] of the commands sent by the module,
+    /// and pass it to `SabiModule::restore_state` if the module is restarted after a failure
+    pub const COMMAND_HISTORY: Self = Self(1 << 3);
+    /// The host can delay creating the module until one of the activities declared
+    /// in `ModuleBuilder::activities` is visible
+    pub const LAZY_INIT: Self = Self(1 << 4);
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+}
+
+impl std::ops::BitOr for ModuleFlags {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Icon of a module
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModuleIcon {
+    /// Name of an icon from the icon theme, like `audio-x-generic-symbolic`
+    Named(RStr<'static>),
+    /// An svg image embedded in the module, like `include_bytes!("icon.svg")`
+    Svg(RSlice<'static, u8>),
 }
 
 impl RootModule for ModuleBuilderRef {
@@ -153,40 +546,578 @@ impl RootModule for ModuleBuilderRef {
     const VERSION_STRINGS: VersionStrings = package_version_strings!();
 }
 
+/// Root module for a library that exports more than one module, like a battery, brightness and volume suite
+///
+/// # Examples
+/// ```ignore
+/// #[export_root_module]
+/// pub fn instantiate_root_module() -> ModuleSuiteRef {
+///     static MODULES: [ModuleBuilderRef; 2] = [battery::BUILDER, brightness::BUILDER];
+///     ModuleSuite {
+///         name: "power-suite".into(),
+///         modules: RSlice::from_slice(&MODULES),
+///     }
+///     .leak_into_prefix()
+/// }
+/// ```
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = ModuleSuiteRef)))]
+#[sabi(missing_field(panic))]
+pub struct ModuleSuite {
+    /// The name of the suite, only used for diagnostics
+    pub name: RStr<'static>,
+
+    /// The modules exported by the library, each one is loaded as if it was in its own library
+    #[sabi(last_prefix_field)]
+    pub modules: RSlice<'static, ModuleBuilderRef>,
+}
+
+impl RootModule for ModuleSuiteRef {
+    declare_root_module_statics! {ModuleSuiteRef}
+    const BASE_NAME: &'static str = "module_suite";
+    const NAME: &'static str = "module_suite";
+    const VERSION_STRINGS: VersionStrings = package_version_strings!();
+}
+
+impl ModuleBuilderRef {
+    /// Name of the config section of this module, `config_key` if set or the module name
+    pub fn config_section(&self) -> RStr<'static> {
+        match self.config_key() {
+            ROption::RSome(key) if !key.is_empty() => key,
+            _ => self.name(),
+        }
+    }
+
+    /// Check if this module can run on the host with version `host_version`
+    pub fn check_host_version(&self, host_version: &str) -> Result<(), VersionError> {
+        check_min_version(self.min_dynisland_version().as_str(), host_version)
+    }
+
+    /// Features both the host and the module will use, the result of `negotiate` without the ones the host doesn't support
+    pub fn negotiate_features(&self, host_features: &[&str]) -> RVec<RString> {
+        let ROption::RSome(negotiate) = self.negotiate() else {
+            return RVec::new();
+        };
+        let mut features = negotiate(host_features.iter().map(|f| RString::from(*f)).collect());
+        features.retain(|feature| host_features.contains(&feature.as_str()));
+        features
+    }
+
+    /// Default config of the module from `default_config`,
+    /// a [`NotImplementedError`] if the module doesn't provide it
+    pub fn static_default_config(&self) -> RResult<RString, RBoxError> {
+        match self.default_config() {
+            ROption::RSome(default_config) => default_config(),
+            ROption::RNone => RResult::RErr(RBoxError::new(NotImplementedError::default())),
+        }
+    }
+
+    /// Destroy `module` with `drop_module`, or drop it in the host if the module doesn't provide it
+    pub fn drop_instance(&self, module: ModuleType) {
+        match self.drop_module() {
+            ROption::RSome(drop_module) => drop_module(module),
+            ROption::RNone => drop(module),
+        }
+    }
+
+    /// Create an instance of the module configured with `config`,
+    /// using `new_with_config` if the module provides it, `new` and `update_config` otherwise
+    pub fn new_configured(
+        &self,
+        app_send: RSender<UIServerCommand>,
+        config: RString,
+    ) -> RResult<ModuleType, RBoxError> {
+        if let ROption::RSome(new_with_config) = self.new_with_config() {
+            return new_with_config(app_send, config);
+        }
+        let mut module = match (self.new())(app_send) {
+            RResult::ROk(module) => module,
+            RResult::RErr(err) => return RResult::RErr(err),
+        };
+        match module.update_config(config) {
+            RResult::ROk(()) => RResult::ROk(module),
+            RResult::RErr(err) => RResult::RErr(RBoxError::new(err)),
+        }
+    }
+}
+
 /// A command from a module to the app thread
 #[repr(C)]
 #[derive(StableAbi)]
+// UpdateActivityMetadata carries two metadata, boxing one would only move the allocation to the module
+#[allow(clippy::large_enum_variant)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum UIServerCommand {
-    /// Add an ActivityWidget to the LayoutManager
+    /// Add an ActivityWidget to the LayoutManager.
+    ///
+    /// If `reply` is set the host sends the result of the registration,
+    /// for example an error if an activity with the same identifier already exists
     AddActivity {
         activity_id: ActivityIdentifier,
+        #[cfg_attr(feature = "serde", serde(skip))]
         widget: SabiWidget,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        reply: ROption<RSender<RResult<(), RBoxError>>>,
     },
     // AddProducer(RString, Producer),
     /// Remove an ActivityWidget from the LayoutManager.
     ///
-    /// The module should drop all the other references to the widget before sending this command
-    RemoveActivity { activity_id: ActivityIdentifier },
+    /// The module should drop all the other references to the widget before sending this command,
+    /// if `reply` is set the host sends an error if the activity doesn't exist
+    RemoveActivity {
+        activity_id: ActivityIdentifier,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        reply: ROption<RSender<RResult<(), RBoxError>>>,
+    },
     /// Send a request for the app to call `SabiModule::restart_producers()`.
     ///
     /// This is useful when you don't have a reference to the module
     RestartProducers { module_name: RString },
 
+    /// Ask the LayoutManager to show the activity in `mode` for `duration` milliseconds.
+    ///
+    /// While do not disturb is enabled the host drops the request, unless `bypass_dnd` is set.
+    /// Use [`NotificationRequest`](crate::notification::NotificationRequest) to build this command
     RequestNotification {
         activity_id: ActivityIdentifier,
-        mode: u8,
+        mode: ActivityMode,
         duration: ROption<u64>,
+        bypass_dnd: bool,
+    },
+    /// Send a request to the brightness service of the host.
+    ///
+    /// See [`BrightnessRequest`]
+    Brightness { request: BrightnessRequest },
+    /// Update multiple keys of the additional metadata of a registered activity at once.
+    ///
+    /// The keys in `remove` are removed first, then the keys in `set` are inserted,
+    /// so if a key is in both it will be set. Keys not mentioned are left unchanged.
+    /// The host applies the whole update before notifying the LayoutManager
+    /// with `SabiLayoutManager::activity_metadata_changed`, so it never sees a partially updated state.
+    ///
+    /// Use [`MetadataUpdateBuilder`](crate::activity_identifier::MetadataUpdateBuilder) to build this command
+    UpdateMetadataBulk {
+        activity_id: ActivityIdentifier,
+        set: RHashMap<RString, RString>,
+        remove: RVec<RString>,
+    },
+    /// Ask the host to show `cursor` while the pointer is over the activity.
+    ///
+    /// The host applies it to the surface containing the activity,
+    /// use `CursorKind::Default` to go back to the cursor of the LayoutManager
+    SetCursorHint {
+        activity_id: ActivityIdentifier,
+        cursor: CursorKind,
+    },
+    /// Ask the host to move the activity to another stacking layer, like a volume OSD.
+    ///
+    /// After `timeout` milliseconds the activity goes back to `OverlayLayer::Normal`,
+    /// if `timeout` is `RNone` it stays there until another `SetLayer` is sent.
+    /// If multiple activities are on the same layer the last one is on top
+    SetLayer {
+        activity_id: ActivityIdentifier,
+        layer: OverlayLayer,
+        timeout: ROption<u64>,
+    },
+    /// Set the tooltip the host shows while the pointer is over the activity, `RNone` removes it
+    SetTooltip {
+        activity_id: ActivityIdentifier,
+        tooltip: ROption<Tooltip>,
+    },
+    /// Ask the host if do not disturb is enabled, changes are sent with `AppEvent::DoNotDisturbChanged`
+    QueryDoNotDisturb {
+        #[cfg_attr(feature = "serde", serde(skip))]
+        reply: RSender<bool>,
+    },
+    /// Register a producer thread of the module, the host may change its niceness
+    /// according to the `scheduling` hint of the module.
+    ///
+    /// `thread_id` is the linux thread id, see [`crate::scheduling::register_current_thread`]
+    RegisterProducerThread {
+        module_name: RString,
+        thread_id: i32,
+    },
+    /// Ask the host to forward the raw key events to the activity instead of the LayoutManager,
+    /// the events are sent to `SabiModule::on_key_event`.
+    ///
+    /// The host decides if the grab is granted (for example only while the activity is focused
+    /// and the user allowed grabs for the module) and what keys are never forwarded (like the ones to release the grab),
+    /// the result is sent to `reply`. Only one activity can hold a grab, a new grab replaces the old one
+    RequestInputGrab {
+        activity_id: ActivityIdentifier,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        reply: ROption<RSender<bool>>,
+    },
+    /// Stop forwarding the key events to the activity
+    ReleaseInputGrab { activity_id: ActivityIdentifier },
+    /// Replace the metadata of a registered activity, the module and activity name must be the same.
+    ///
+    /// The host stores the new metadata and then calls `SabiLayoutManager::activity_metadata_changed`
+    /// with the updated identifier, the widget is not recreated
+    UpdateActivityMetadata {
+        activity_id: ActivityIdentifier,
+        metadata: ActivityMetadata,
+    },
+    /// Show or hide a registered activity without removing it, the activity keeps its position in the layout.
+    ///
+    /// Useful for activities that have nothing to show for a while, like a music player when nothing is playing,
+    /// the host forwards it to `SabiLayoutManager::set_activity_visibility`
+    SetActivityVisibility {
+        activity_id: ActivityIdentifier,
+        visible: bool,
+    },
+    /// Ask the LayoutManager to expand or raise the activity, for example for an incoming call or a finished timer.
+    ///
+    /// The LayoutManager is free to ignore it, see `SabiLayoutManager::request_focus`
+    RequestFocus { activity_id: ActivityIdentifier },
+    /// Change the position of the activity relative to the other activities of the same window.
+    ///
+    /// Activities with a higher weight come first, like `ModuleBuilder::priority`, the default weight is 0
+    /// and activities with the same weight keep the registration order.
+    /// The host forwards it to `SabiLayoutManager::set_activity_order`
+    SetActivityOrder {
+        activity_id: ActivityIdentifier,
+        weight: i32,
+    },
+    /// Multiple commands delivered at once, like all the activities registered at startup.
+    ///
+    /// The host executes them in order and updates the layout once at the end instead of after every command.
+    /// Batches can be nested, see [`UIServerCommand::flatten`]
+    Batch(RVec<UIServerCommand>),
+    /// Ask the host to read the config file again and call `update_config` of the module with its section,
+    /// for example after the module noticed that its section changed or from a cli command
+    RequestConfigReload { module_name: RString },
+    /// A log message of the module, the host writes it to its log output and to `dynisland log`.
+    ///
+    /// For modules that can't use the logger from `SabiModule::set_logger`,
+    /// see [`command_logger`](crate::logger::command_logger)
+    Log {
+        module_name: RString,
+        level: LogLevel,
+        message: RString,
+    },
+    /// Ask the host which activities of the module are registered,
+    /// the identifiers (with their current metadata) are sent to `reply`
+    QueryActivities {
+        module_name: RString,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        reply: RSender<RVec<ActivityIdentifier>>,
+    },
+    /// Show a transient notification rendered by the host in the dynamic island, attributed to the activity,
+    /// the module doesn't need a widget for it.
+    ///
+    /// While do not disturb is enabled only the critical notifications are shown
+    ShowNotification {
+        activity_id: ActivityIdentifier,
+        content: NotificationContent,
+    },
+    /// Close the notifications of the activity before their duration ends,
+    /// both the ones shown with `ShowNotification` and the mode change of `RequestNotification`.
+    ///
+    /// It does nothing if the activity has no notification shown
+    DismissNotification { activity_id: ActivityIdentifier },
+    /// Show `widget` in a popup anchored to the activity, like a volume slider or a calendar,
+    /// instead of creating a layer shell window in the module.
+    ///
+    /// The popup is closed by the LayoutManager when it loses focus, with `ClosePopup`, or when the activity is removed.
+    /// An activity has at most one popup, a new one replaces the old one
+    OpenPopup {
+        activity_id: ActivityIdentifier,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        widget: SabiWidget,
+        anchor: PopupAnchor,
+    },
+    /// Close the popup opened with `OpenPopup`
+    ClosePopup { activity_id: ActivityIdentifier },
+    /// Move the activity to another window or monitor, for modules with per-monitor activities.
+    ///
+    /// For `OutputSelector::Window` the host also updates the `window_name` in the metadata,
+    /// if the target doesn't exist the activity stays where it is.
+    /// The host forwards it to `SabiLayoutManager::move_activity`
+    MoveActivity {
+        activity_id: ActivityIdentifier,
+        target: OutputSelector,
+    },
+    /// Mark the activity as needing attention (unread messages, a finished build) or clear the mark,
+    /// the LayoutManager renders the urgency with a consistent style, see `SabiLayoutManager::set_urgency`
+    SetUrgency {
+        activity_id: ActivityIdentifier,
+        urgent: bool,
+    },
+    /// Replace the widget of a registered activity, for example after a major config change,
+    /// the activity keeps its identity and position in the layout.
+    ///
+    /// The host forwards it to `SabiLayoutManager::replace_activity_widget`
+    /// and reports an error to the module with a [`CommandError`](crate::feedback::CommandError),
+    /// the module should drop all the other references to the old widget
+    ReplaceActivityWidget {
+        activity_id: ActivityIdentifier,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        widget: SabiWidget,
+    },
+    /// Change the name of a registered activity, keeping its widget, metadata and position,
+    /// for activities that follow external objects (players, networks) that can be renamed.
+    ///
+    /// The module must also change the name of the widget to `new_name`.
+    /// The host calls `SabiLayoutManager::rename_activity` and, if it succeeds, updates its registry with [`ActivityIdentifier::renamed`],
+    /// otherwise it reports the error to the module with a [`CommandError`](crate::feedback::CommandError)
+    RenameActivity {
+        old_id: ActivityIdentifier,
+        new_name: RString,
+    },
+    /// Take a snapshot of the activity as it's currently rendered, for debugging, previews or sharing.
+    ///
+    /// The host asks the LayoutManager with `SabiLayoutManager::capture_activity` and sends the result to `reply`
+    CaptureActivity {
+        activity_id: ActivityIdentifier,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        reply: RSender<RResult<EncodedImage, RBoxError>>,
+    },
+    /// Play a sound with the audio client of the host, like a feedback for an interaction.
+    ///
+    /// The host can ignore it, for example if do not disturb is enabled
+    PlaySound { sound: SoundSpec },
+    /// Replace the css classes the module set on the container of the activity, like `charging` or `muted`.
+    ///
+    /// An empty list removes all of them, the classes added by the LayoutManager are not affected
+    SetActivityCssClasses {
+        activity_id: ActivityIdentifier,
+        classes: RVec<RString>,
+    },
+    /// Ask the host to unload the module, for example if the hardware or the daemon it needs is missing.
+    ///
+    /// The host removes all the activities of the module and drops it,
+    /// the module must not send other commands after this one
+    UnloadModule { module_name: RString },
+    /// Ask the host to reserve `size` pixels of the screen (the layer-shell exclusive zone)
+    /// for the window of the activity, for example while it's expanded in a large panel.
+    ///
+    /// `RNone` releases the reservation. If more activities of the same window request a zone the biggest is used
+    RequestExclusiveZone {
+        activity_id: ActivityIdentifier,
+        size: ROption<i32>,
+    },
+    /// Ask the host to change the layer-shell layer, anchors or margins of a window,
+    /// `window` is the name used in `ActivityMetadata::window_name` (empty for the default window)
+    RequestWindowProperties { window: RString, props: WindowProps },
+    /// Receive the events of the streams in `events` on `sender`, replacing the previous subscription of the module.
+    ///
+    /// The host forwards only the streams someone is subscribed to, `EventMask::NONE` cancels the subscription
+    Subscribe {
+        module_name: RString,
+        events: EventMask,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        sender: RSender<ModuleEvent>,
+    },
+    /// Send `payload` to the module named `target_module`, the host calls `SabiModule::on_module_message` on it.
+    ///
+    /// If `reply` is set the answer of the target is sent to it,
+    /// the sender is dropped without a message if the target doesn't exist or doesn't answer
+    SendToModule {
+        target_module: RString,
+        payload: RVec<u8>,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        reply: ROption<RSender<RVec<u8>>>,
+    },
+    /// Ask the host to call `SabiModule::on_timer(token)` after `after_ms` milliseconds, and then every `after_ms` if `repeat` is set.
+    ///
+    /// Scheduling a token that is already scheduled replaces its timer, `after_ms` of 0 cancels it
+    Schedule {
+        module_name: RString,
+        token: u64,
+        after_ms: u64,
+        repeat: bool,
+    },
+    /// Open `uri` with the default handler of its scheme, like a browser for `https://`
+    OpenUri { uri: RString },
+    /// Launch the application with the desktop file id `desktop_id` (like `org.gnome.Nautilus.desktop`),
+    /// `args` are passed as uris or files to the application
+    LaunchApp {
+        desktop_id: RString,
+        args: RVec<RString>,
+    },
+    /// Put `data` in the clipboard with the mime type `mime`, like `text/plain;charset=utf-8`
+    ClipboardSet { mime: RString, data: RVec<u8> },
+    /// Read the content of the clipboard as `mime`,
+    /// the reply is an error if the clipboard is empty or the content can't be converted to `mime`
+    ClipboardGet {
+        mime: RString,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        reply: RSender<RResult<RVec<u8>, RBoxError>>,
+    },
+    /// Report the progress of an operation of the activity, like a download.
+    ///
+    /// `token` identifies the operation, more operations can run at the same time.
+    /// `fraction` goes from 0.0 to 1.0, a negative value means the progress is unknown,
+    /// reaching 1.0 completes the operation and removes its indicator
+    ReportProgress {
+        activity_id: ActivityIdentifier,
+        token: u64,
+        fraction: f64,
+        label: ROption<RString>,
+    },
+    /// Accept drag and drop of data of the types in `mime_types` (like `text/uri-list` or `text/plain`) on the activity,
+    /// the LayoutManager sends the `DropEvent`s to the module. An empty list disables it
+    SetDropTarget {
+        activity_id: ActivityIdentifier,
+        mime_types: RVec<RString>,
+    },
+    /// Register a keyboard shortcut, the host sends `ModuleServerEvent::ShortcutPressed` with `shortcut_id` when it's pressed.
+    ///
+    /// `trigger` uses the gtk accelerator format, like `<Super>m`. The user can override it in the config of the host,
+    /// registering an id again replaces its trigger
+    RegisterShortcut {
+        module_name: RString,
+        shortcut_id: RString,
+        trigger: RString,
+        scope: ShortcutScope,
+    },
+    UnregisterShortcut {
+        module_name: RString,
+        shortcut_id: RString,
+    },
+    /// Run a closure on the thread of the main context of the host, see [`MainTask`].
+    ///
+    /// The host must run `task` from the main context, never inline on the thread that received the command
+    RunOnMain {
+        #[cfg_attr(feature = "serde", serde(skip))]
+        task: MainTask,
     },
 }
 
+impl UIServerCommand {
+    /// Name of the variant, used for logging and diagnostics
+    pub fn name(&self) -> &'static str {
+        match self {
+            UIServerCommand::AddActivity { .. } => "AddActivity",
+            UIServerCommand::RemoveActivity { .. } => "RemoveActivity",
+            UIServerCommand::RestartProducers { .. } => "RestartProducers",
+            UIServerCommand::RequestNotification { .. } => "RequestNotification",
+            UIServerCommand::Brightness { .. } => "Brightness",
+            UIServerCommand::UpdateMetadataBulk { .. } => "UpdateMetadataBulk",
+            UIServerCommand::SetCursorHint { .. } => "SetCursorHint",
+            UIServerCommand::SetLayer { .. } => "SetLayer",
+            UIServerCommand::SetTooltip { .. } => "SetTooltip",
+            UIServerCommand::QueryDoNotDisturb { .. } => "QueryDoNotDisturb",
+            UIServerCommand::RegisterProducerThread { .. } => "RegisterProducerThread",
+            UIServerCommand::RequestInputGrab { .. } => "RequestInputGrab",
+            UIServerCommand::ReleaseInputGrab { .. } => "ReleaseInputGrab",
+            UIServerCommand::UpdateActivityMetadata { .. } => "UpdateActivityMetadata",
+            UIServerCommand::SetActivityVisibility { .. } => "SetActivityVisibility",
+            UIServerCommand::RequestFocus { .. } => "RequestFocus",
+            UIServerCommand::SetActivityOrder { .. } => "SetActivityOrder",
+            UIServerCommand::Batch(..) => "Batch",
+            UIServerCommand::RequestConfigReload { .. } => "RequestConfigReload",
+            UIServerCommand::Log { .. } => "Log",
+            UIServerCommand::QueryActivities { .. } => "QueryActivities",
+            UIServerCommand::ShowNotification { .. } => "ShowNotification",
+            UIServerCommand::DismissNotification { .. } => "DismissNotification",
+            UIServerCommand::OpenPopup { .. } => "OpenPopup",
+            UIServerCommand::ClosePopup { .. } => "ClosePopup",
+            UIServerCommand::MoveActivity { .. } => "MoveActivity",
+            UIServerCommand::SetUrgency { .. } => "SetUrgency",
+            UIServerCommand::ReplaceActivityWidget { .. } => "ReplaceActivityWidget",
+            UIServerCommand::RenameActivity { .. } => "RenameActivity",
+            UIServerCommand::CaptureActivity { .. } => "CaptureActivity",
+            UIServerCommand::PlaySound { .. } => "PlaySound",
+            UIServerCommand::SetActivityCssClasses { .. } => "SetActivityCssClasses",
+            UIServerCommand::UnloadModule { .. } => "UnloadModule",
+            UIServerCommand::RequestExclusiveZone { .. } => "RequestExclusiveZone",
+            UIServerCommand::RequestWindowProperties { .. } => "RequestWindowProperties",
+            UIServerCommand::Subscribe { .. } => "Subscribe",
+            UIServerCommand::SendToModule { .. } => "SendToModule",
+            UIServerCommand::Schedule { .. } => "Schedule",
+            UIServerCommand::OpenUri { .. } => "OpenUri",
+            UIServerCommand::LaunchApp { .. } => "LaunchApp",
+            UIServerCommand::ClipboardSet { .. } => "ClipboardSet",
+            UIServerCommand::ClipboardGet { .. } => "ClipboardGet",
+            UIServerCommand::ReportProgress { .. } => "ReportProgress",
+            UIServerCommand::SetDropTarget { .. } => "SetDropTarget",
+            UIServerCommand::RegisterShortcut { .. } => "RegisterShortcut",
+            UIServerCommand::UnregisterShortcut { .. } => "UnregisterShortcut",
+            UIServerCommand::RunOnMain { .. } => "RunOnMain",
+        }
+    }
+    /// The commands in this command, expanding the nested batches
+    pub fn flatten(self) -> Vec<UIServerCommand> {
+        let mut commands = Vec::new();
+        let mut stack = vec![self];
+        while let Some(command) = stack.pop() {
+            match command {
+                UIServerCommand::Batch(batch) => stack.extend(batch.into_iter().rev()),
+                command => commands.push(command),
+            }
+        }
+        commands
+    }
+    /// The activity this command refers to, if any
+    pub fn activity_id(&self) -> Option<&ActivityIdentifier> {
+        match self {
+            UIServerCommand::AddActivity { activity_id, .. }
+            | UIServerCommand::RemoveActivity { activity_id, .. }
+            | UIServerCommand::RequestNotification { activity_id, .. }
+            | UIServerCommand::UpdateMetadataBulk { activity_id, .. }
+            | UIServerCommand::SetCursorHint { activity_id, .. }
+            | UIServerCommand::SetLayer { activity_id, .. }
+            | UIServerCommand::SetTooltip { activity_id, .. }
+            | UIServerCommand::RequestInputGrab { activity_id, .. }
+            | UIServerCommand::ReleaseInputGrab { activity_id }
+            | UIServerCommand::UpdateActivityMetadata { activity_id, .. }
+            | UIServerCommand::SetActivityVisibility { activity_id, .. }
+            | UIServerCommand::RequestFocus { activity_id }
+            | UIServerCommand::SetActivityOrder { activity_id, .. }
+            | UIServerCommand::ShowNotification { activity_id, .. }
+            | UIServerCommand::DismissNotification { activity_id }
+            | UIServerCommand::OpenPopup { activity_id, .. }
+            | UIServerCommand::ClosePopup { activity_id }
+            | UIServerCommand::MoveActivity { activity_id, .. }
+            | UIServerCommand::SetUrgency { activity_id, .. }
+            | UIServerCommand::ReplaceActivityWidget { activity_id, .. }
+            | UIServerCommand::RenameActivity {
+                old_id: activity_id,
+                ..
+            }
+            | UIServerCommand::CaptureActivity { activity_id, .. }
+            | UIServerCommand::SetActivityCssClasses { activity_id, .. }
+            | UIServerCommand::RequestExclusiveZone { activity_id, .. }
+            | UIServerCommand::ReportProgress { activity_id, .. }
+            | UIServerCommand::SetDropTarget { activity_id, .. } => Some(activity_id),
+            UIServerCommand::RestartProducers { .. }
+            | UIServerCommand::Brightness { .. }
+            | UIServerCommand::QueryDoNotDisturb { .. }
+            | UIServerCommand::RegisterProducerThread { .. }
+            | UIServerCommand::Batch(..)
+            | UIServerCommand::RequestConfigReload { .. }
+            | UIServerCommand::Log { .. }
+            | UIServerCommand::QueryActivities { .. }
+            | UIServerCommand::PlaySound { .. }
+            | UIServerCommand::UnloadModule { .. }
+            | UIServerCommand::RequestWindowProperties { .. }
+            | UIServerCommand::Subscribe { .. }
+            | UIServerCommand::SendToModule { .. }
+            | UIServerCommand::Schedule { .. }
+            | UIServerCommand::OpenUri { .. }
+            | UIServerCommand::LaunchApp { .. }
+            | UIServerCommand::ClipboardSet { .. }
+            | UIServerCommand::ClipboardGet { .. }
+            | UIServerCommand::RegisterShortcut { .. }
+            | UIServerCommand::UnregisterShortcut { .. }
+            | UIServerCommand::RunOnMain { .. } => None,
+        }
+    }
+}
+
 /// Module and activity name, used to uniquely identify a dynamic activity
 ///
 /// Also includes metadata, this is not used for identification but for additional information
 /// storage and comunication from the module to the layout manager
 ///
-/// This struct must not change once the activity is registered
+/// The module and activity name must not change once the activity is registered,
+/// the metadata can be replaced with `UIServerCommand::UpdateActivityMetadata`
 #[repr(C)]
 #[derive(StableAbi, Clone, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ActivityIdentifier {
     /// Module name, must be the same as the on provided in `ModuleBuilder`
     pub(crate) module: RString,
@@ -212,11 +1143,30 @@ impl PartialEq for ActivityIdentifier {
 
 #[repr(C)]
 #[derive(StableAbi, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ActivityMetadata {
     pub(crate) window_name: ROption<RString>,
 
     #[sabi(last_prefix_field)]
     pub(crate) additional_metadata: RHashMap<RString, RString>,
+
+    /// Name to show to the user, the activity name is used if this is not set
+    pub(crate) display_name: ROption<LocalizedString>,
+
+    /// Policy the host must enforce on the web content embedded in the widget, see [`crate::web_content`]
+    pub(crate) web_content_policy: ROption<WebContentPolicy>,
+
+    /// The activity should be shown only when this is true, see [`crate::condition`]
+    pub(crate) visibility_condition: ROption<Condition>,
+
+    /// Typed additional metadata, separate from the string entries, see [`crate::metadata`]
+    pub(crate) typed_metadata: RHashMap<RString, MetadataValue>,
+
+    /// Size hints for the LayoutManager, see [`SizeHints`]
+    pub(crate) size_hints: ROption<SizeHints>,
+
+    /// Placement hints for the LayoutManager, see [`PlacementHint`]
+    pub(crate) placement: ROption<PlacementHint>,
 }
 
 impl PartialOrd for ActivityMetadata {
@@ -230,9 +1180,69 @@ impl Ord for ActivityMetadata {
     }
 }
 
+/// Generate the `new` function, the [`ModuleBuilder`] and the exported root module of a module crate.
+///
+/// `$module` must implement [`SabiModule`] and have a `fn new(app_send: RSender<UIServerCommand>) -> Self`,
+/// the version of the module is the version of the crate and `drop_module` is set,
+/// a panic in `new` is returned as an error, see [`catch_abi_panic!`](crate::catch_abi_panic).
+/// The other fields of the `ModuleBuilder` can be set after the name.
+/// The crate must depend on `abi_stable` because the generated code uses its attribute macros
+///
+/// # Examples
+/// ```ignore
+/// pub const NAME: &str = "music-module";
+///
+/// declare_module!(MusicModule, NAME, description: RStr::from_str("Show the current song"));
+/// ```
+#[macro_export]
+macro_rules! declare_module {
+    ($module:ty, $name:expr $(, $field:ident : $value:expr)* $(,)?) => {
+        #[$crate::abi_stable::sabi_extern_fn]
+        pub fn new(
+            app_send: $crate::abi_stable::external_types::crossbeam_channel::RSender<
+                $crate::module::UIServerCommand,
+            >,
+        ) -> $crate::abi_stable::std_types::RResult<
+            $crate::module::ModuleType,
+            $crate::abi_stable::std_types::RBoxError,
+        > {
+            $crate::catch_abi_panic!({
+                let module = <$module>::new(app_send);
+                $crate::abi_stable::std_types::RResult::ROk(
+                    $crate::module::SabiModule_TO::from_value(
+                        module,
+                        $crate::abi_stable::sabi_trait::TD_CanDowncast,
+                    ),
+                )
+            })
+        }
+
+        #[$crate::abi_stable::sabi_extern_fn]
+        fn drop_module(module: $crate::module::ModuleType) {
+            drop(module)
+        }
+
+        #[$crate::abi_stable::export_root_module]
+        pub fn instantiate_root_module() -> $crate::module::ModuleBuilderRef {
+            use $crate::abi_stable::prefix_type::PrefixTypeTrait;
+            $crate::module::ModuleBuilder {
+                version: $crate::abi_stable::std_types::RStr::from_str(env!("CARGO_PKG_VERSION")),
+                drop_module: $crate::abi_stable::std_types::ROption::RSome(drop_module),
+                $($field: $value,)*
+                ..$crate::module::ModuleBuilder::with_defaults(new, $name)
+            }
+            .leak_into_prefix()
+        }
+    };
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::activity_identifier::{
+        NameErrorKind, NameField, ParseActivityIdentifierError, MAX_NAME_LEN,
+    };
+    use crate::metadata::PlacementEdge;
 
     #[test]
     fn test_activity_identifier_hash() {
@@ -280,4 +1290,191 @@ mod test {
         let cmp = act.cmp(&act2);
         assert_eq!(cmp, std::cmp::Ordering::Equal);
     }
+
+    #[test]
+    fn test_batch_flatten() {
+        let remove = |name: &str| UIServerCommand::RemoveActivity {
+            activity_id: ActivityIdentifier::new("module", name),
+            reply: ROption::RNone,
+        };
+        let batch = UIServerCommand::Batch(
+            vec![
+                remove("a"),
+                UIServerCommand::Batch(vec![remove("b"), remove("c")].into()),
+                remove("d"),
+            ]
+            .into(),
+        );
+        let commands = batch.flatten();
+        let names: Vec<_> = commands
+            .iter()
+            .map(|command| command.activity_id().unwrap().activity())
+            .collect();
+        assert_eq!(names, ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_metadata_bulk_update() {
+        let mut act = ActivityIdentifier::new("module", "activity");
+        act.metadata_mut()
+            .set_additional_metadata("keep".to_string(), "1".to_string());
+        act.metadata_mut()
+            .set_additional_metadata("old".to_string(), "1".to_string());
+
+        let command = crate::activity_identifier::MetadataUpdateBuilder::new(&act)
+            .remove("old")
+            .remove("new")
+            .set("new", "2")
+            .set("other", "3")
+            .remove("other")
+            .build();
+        let UIServerCommand::UpdateMetadataBulk { set, remove, .. } = command else {
+            panic!("wrong command");
+        };
+        act.metadata_mut().apply_bulk_update(&set, &remove);
+
+        let metadata = act.metadata();
+        assert_eq!(metadata.additional_metadata("keep"), Some("1".to_string()));
+        assert_eq!(metadata.additional_metadata("old"), None);
+        assert_eq!(metadata.additional_metadata("new"), Some("2".to_string()));
+        assert_eq!(metadata.additional_metadata("other"), None);
+    }
+
+    #[test]
+    fn test_activity_identifier_parse() {
+        let id: ActivityIdentifier = "player@music-module".parse().unwrap();
+        assert_eq!(id.activity(), "player");
+        assert_eq!(id.module(), "music-module");
+        assert_eq!(id.to_string().parse::<ActivityIdentifier>(), Ok(id));
+        assert_eq!(
+            "player".parse::<ActivityIdentifier>(),
+            Err(ParseActivityIdentifierError::MissingSeparator)
+        );
+        let Err(ParseActivityIdentifierError::InvalidName(err)) =
+            "user@example.com@mail".parse::<ActivityIdentifier>()
+        else {
+            panic!("an activity name with @ was accepted");
+        };
+        assert_eq!(err.field, NameField::Activity);
+        assert_eq!(err.kind, NameErrorKind::InvalidChar('@'));
+    }
+
+    #[test]
+    fn test_activity_identifier_validation() {
+        assert!(ActivityIdentifier::try_new("music", "player").is_ok());
+        let err = ActivityIdentifier::try_new("  ", "player").unwrap_err();
+        assert_eq!(
+            (err.field, err.kind),
+            (NameField::Module, NameErrorKind::Empty)
+        );
+        let long = "x".repeat(MAX_NAME_LEN + 1);
+        assert_eq!(
+            ActivityIdentifier::try_new("music", &long)
+                .unwrap_err()
+                .kind,
+            NameErrorKind::TooLong {
+                len: MAX_NAME_LEN + 1
+            }
+        );
+        assert!(!ActivityIdentifier::new("music", "line\nbreak").is_valid());
+        assert!(ActivityIdentifier::new("music", "player").is_valid());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_activity_identifier() {
+        let mut id = ActivityIdentifier::new("music", "player");
+        id.metadata_mut().set_window_name("top");
+        id.metadata_mut()
+            .set_additional_metadata("artist".into(), "someone".into());
+        let json = serde_json::to_string(&id).unwrap();
+        let parsed: ActivityIdentifier = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, id);
+        assert_eq!(parsed.metadata, id.metadata);
+
+        let command = UIServerCommand::RemoveActivity {
+            activity_id: id,
+            reply: ROption::RNone,
+        };
+        let json = serde_json::to_value(&command).unwrap();
+        assert_eq!(json["RemoveActivity"]["activity_id"]["activity"], "player");
+    }
+
+    #[test]
+    fn test_typed_metadata() {
+        let mut metadata = ActivityMetadata::new();
+        metadata.set_value("progress", 0.5);
+        metadata.set_value("count", 3i64);
+        metadata.set_value("tags", vec!["a".to_string(), "b".to_string()]);
+        metadata.set_additional_metadata("count".into(), "not a number".into());
+        assert_eq!(metadata.float_value("progress"), Some(0.5));
+        assert_eq!(metadata.int_value("count"), Some(3));
+        assert_eq!(metadata.float_value("count"), Some(3.0));
+        assert_eq!(metadata.bool_value("count"), None);
+        assert_eq!(
+            metadata.string_list_value("tags").map(|tags| tags.len()),
+            Some(2)
+        );
+        assert_eq!(
+            metadata.additional_metadata("count"),
+            Some("not a number".to_string())
+        );
+        metadata.set_value("nan", f64::NAN);
+        assert_eq!(metadata, metadata.clone());
+    }
+
+    #[test]
+    fn test_activity_identifier_builder() {
+        let id = ActivityIdentifier::builder("music", "player")
+            .window_name("top")
+            .meta("artist", "someone")
+            .value("volume", 0.8)
+            .build();
+        let mut expected = ActivityIdentifier::new("music", "player");
+        expected.metadata_mut().set_window_name("top");
+        expected
+            .metadata_mut()
+            .set_additional_metadata("artist".into(), "someone".into());
+        expected.metadata_mut().set_value("volume", 0.8);
+        assert_eq!(id, expected);
+        assert_eq!(id.metadata, expected.metadata);
+
+        let placement = PlacementHint::default()
+            .edge(PlacementEdge::Right)
+            .output(OutputSelector::Primary);
+        let id = ActivityIdentifier::builder("music", "player")
+            .placement(placement.clone())
+            .build();
+        assert_eq!(id.metadata.placement(), Some(&placement));
+        assert_eq!(id.metadata.placement().unwrap().align, ROption::RNone);
+    }
+
+    #[test]
+    fn test_handle_event_dispatch() {
+        struct TimerModule(std::sync::atomic::AtomicU64);
+        impl SabiModule for TimerModule {
+            fn init(&self) {}
+            fn update_config(&mut self, _config: RString) -> RResult<(), ConfigError> {
+                RResult::ROk(())
+            }
+            fn restart_producers(&self) {}
+            fn on_timer(&self, token: u64) {
+                self.0.store(token, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+        let module = TimerModule(std::sync::atomic::AtomicU64::new(0));
+        assert!(module.handle_event(ModuleServerEvent::Timer { token: 42 }));
+        assert_eq!(module.0.load(std::sync::atomic::Ordering::Relaxed), 42);
+        let key = ModuleServerEvent::Key {
+            activity_id: ActivityIdentifier::new("module", "activity"),
+            event: KeyEvent {
+                keyval: 0,
+                keycode: 0,
+                modifiers: Default::default(),
+                pressed: true,
+                text: ROption::RNone,
+            },
+        };
+        assert!(!module.handle_event(key));
+    }
 }
@@ -0,0 +1,146 @@
+use abi_stable::{
+    external_types::crossbeam_channel::RSender,
+    std_types::{ROption, RString, RVec},
+    StableAbi,
+};
+
+use crate::{
+    activity_mode::ActivityMode,
+    module::{ActivityIdentifier, UIServerCommand},
+};
+
+/// Builder for `UIServerCommand::RequestNotification`
+///
+/// # Examples
+/// ```ignore
+/// let command = NotificationRequest::new(&activity_id, ActivityMode::Expanded)
+///     .duration(5000)
+///     .bypass_dnd(true)
+///     .build();
+/// app_send.send(command).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct NotificationRequest {
+    activity_id: ActivityIdentifier,
+    mode: ActivityMode,
+    duration: ROption<u64>,
+    bypass_dnd: bool,
+}
+
+impl NotificationRequest {
+    pub fn new(activity_id: &ActivityIdentifier, mode: ActivityMode) -> Self {
+        Self {
+            activity_id: activity_id.clone(),
+            mode,
+            duration: ROption::RNone,
+            bypass_dnd: false,
+        }
+    }
+    /// How long the notification is shown in milliseconds, the LayoutManager decides if not set
+    pub fn duration(mut self, duration_ms: u64) -> Self {
+        self.duration = ROption::RSome(duration_ms);
+        self
+    }
+    /// Show the notification even if do not disturb is enabled, this should only be used for alarms and similar
+    pub fn bypass_dnd(mut self, bypass_dnd: bool) -> Self {
+        self.bypass_dnd = bypass_dnd;
+        self
+    }
+    pub fn build(self) -> UIServerCommand {
+        UIServerCommand::RequestNotification {
+            activity_id: self.activity_id,
+            mode: self.mode,
+            duration: self.duration,
+            bypass_dnd: self.bypass_dnd,
+        }
+    }
+}
+
+/// Urgency of a [`NotificationContent`]
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NotificationUrgency {
+    Low,
+    #[default]
+    Normal,
+    /// Shown even if do not disturb is enabled
+    Critical,
+}
+
+/// A button shown in a notification
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NotificationAction {
+    /// Sent to `NotificationContent::action_sender` when the action is invoked
+    pub id: RString,
+
+    #[sabi(last_prefix_field)]
+    pub label: RString,
+}
+
+/// Content of a transient notification rendered by the host, see `UIServerCommand::ShowNotification`
+///
+/// # Examples
+/// ```ignore
+/// let (action_send, action_recv) = unbounded();
+/// let content = NotificationContent::new("Download finished")
+///     .body("file.iso")
+///     .icon("folder-download-symbolic")
+///     .action("open", "Open")
+///     .on_action(action_send);
+/// app_send.send(UIServerCommand::ShowNotification { activity_id, content }).unwrap();
+/// ```
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NotificationContent {
+    pub title: RString,
+    pub body: ROption<RString>,
+    /// Icon name from the icon theme
+    pub icon: ROption<RString>,
+    pub urgency: NotificationUrgency,
+    pub actions: RVec<NotificationAction>,
+
+    /// Receives the id of the actions invoked by the user
+    #[sabi(last_prefix_field)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub action_sender: ROption<RSender<RString>>,
+}
+
+impl NotificationContent {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.into(),
+            body: ROption::RNone,
+            icon: ROption::RNone,
+            urgency: NotificationUrgency::Normal,
+            actions: RVec::new(),
+            action_sender: ROption::RNone,
+        }
+    }
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = ROption::RSome(body.into());
+        self
+    }
+    pub fn icon(mut self, icon_name: &str) -> Self {
+        self.icon = ROption::RSome(icon_name.into());
+        self
+    }
+    pub fn urgency(mut self, urgency: NotificationUrgency) -> Self {
+        self.urgency = urgency;
+        self
+    }
+    pub fn action(mut self, id: &str, label: &str) -> Self {
+        self.actions.push(NotificationAction {
+            id: id.into(),
+            label: label.into(),
+        });
+        self
+    }
+    pub fn on_action(mut self, sender: RSender<RString>) -> Self {
+        self.action_sender = ROption::RSome(sender);
+        self
+    }
+}
@@ -0,0 +1,120 @@
+//! Convert the panics of a module into errors.
+//!
+//! A panic can't unwind through the `extern "C"` functions of the abi, so a panicking module
+//! aborts the whole process. Wrapping the body of the [`SabiModule`](crate::module::SabiModule) methods
+//! and of the `new` function with [`catch_abi_panic!`](crate::catch_abi_panic) turns the panic into a [`PanicError`]
+//! that the host can report, restarting or unloading the module.
+//!
+//! # Examples
+//! ```ignore
+//! fn update_config(&mut self, config: RString) -> RResult<(), ConfigError> {
+//!     catch_abi_panic!({
+//!         self.config = parse(&config).unwrap_or_default();
+//!         ROk(())
+//!     })
+//! }
+//!
+//! fn init(&self) {
+//!     catch_abi_panic!(default = (); {
+//!         self.start_producers();
+//!     })
+//! }
+//! ```
+
+use std::{
+    fmt::Display,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use abi_stable::std_types::{RBoxError, RResult};
+
+/// A panic caught by [`catch_panic`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicError {
+    /// The message of the panic, if it was a string
+    pub message: Option<String>,
+}
+
+impl Display for PanicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "module panicked: {message}"),
+            None => write!(f, "module panicked"),
+        }
+    }
+}
+impl std::error::Error for PanicError {}
+
+impl PanicError {
+    fn from_payload(payload: Box<dyn std::any::Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => Some(*message),
+            Err(payload) => payload.downcast_ref::<&str>().map(|m| m.to_string()),
+        };
+        Self { message }
+    }
+}
+
+impl From<PanicError> for RBoxError {
+    fn from(err: PanicError) -> Self {
+        RBoxError::new(err)
+    }
+}
+
+/// Run `f`, converting a panic into a [`PanicError`]
+pub fn catch_panic<T, E: From<PanicError>>(f: impl FnOnce() -> RResult<T, E>) -> RResult<T, E> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => RResult::RErr(PanicError::from_payload(payload).into()),
+    }
+}
+
+/// Run `f`, logging a panic and returning `default` instead
+pub fn catch_panic_or<T>(default: T, f: impl FnOnce() -> T) -> T {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(payload) => {
+            log::error!("{}", PanicError::from_payload(payload));
+            default
+        }
+    }
+}
+
+/// Catch the panics of a block, see [`crate::panic`].
+///
+/// `catch_abi_panic!(body)` is for blocks that return `RResult<T, RBoxError>` or `RResult<T, ConfigError>`, the panic becomes the error.
+/// `catch_abi_panic!(default = value; body)` is for the other blocks, the panic is logged and `value` is returned
+#[macro_export]
+macro_rules! catch_abi_panic {
+    (default = $default:expr; $body:expr) => {
+        $crate::panic::catch_panic_or($default, || $body)
+    };
+    ($body:expr) => {
+        $crate::panic::catch_panic(|| $body)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_catch_abi_panic() {
+        let result: RResult<(), RBoxError> = catch_abi_panic!({
+            let values: Vec<u32> = Vec::new();
+            values.first().expect("no value");
+            RResult::ROk(())
+        });
+        assert_eq!(result.unwrap_err().to_string(), "module panicked: no value");
+        let ok: RResult<u32, RBoxError> = catch_abi_panic!(RResult::ROk(1));
+        assert_eq!(ok.unwrap(), 1);
+        let value = catch_abi_panic!(default = 0; panic!("formatted {}", 1));
+        assert_eq!(value, 0);
+        let config: RResult<(), crate::config::ConfigError> =
+            catch_abi_panic!(panic!("invalid config"));
+        assert_eq!(
+            config.unwrap_err().message,
+            "module panicked: invalid config"
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use abi_stable::{external_types::crossbeam_channel::RSender, std_types::RString, StableAbi};
+
+use crate::module::UIServerCommand;
+
+/// How much cpu time a module should get compared to the UI thread
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SchedulingHint {
+    /// Same priority as the host
+    #[default]
+    Normal,
+    /// The module does heavy work that can be delayed, like an audio visualizer
+    Background,
+    /// The module work should only run when nothing else needs the cpu
+    Idle,
+}
+
+impl SchedulingHint {
+    /// Niceness the host should apply to the producer threads of the module
+    pub fn nice(&self) -> i32 {
+        match self {
+            SchedulingHint::Normal => 0,
+            SchedulingHint::Background => 10,
+            SchedulingHint::Idle => 19,
+        }
+    }
+}
+
+/// Linux thread id of the current thread, `None` if `/proc` is not available
+pub fn current_thread_id() -> Option<i32> {
+    // `/proc/thread-self` links to `<pid>/task/<tid>`
+    let link = std::fs::read_link("/proc/thread-self").ok()?;
+    link.file_name()?.to_str()?.parse().ok()
+}
+
+/// Register the current thread as a producer thread of `module_name`,
+/// so the host can adjust its priority with the scheduling hint of the module.
+///
+/// Returns `false` if the thread id is not available or the host is gone
+pub fn register_current_thread(app_send: &RSender<UIServerCommand>, module_name: &str) -> bool {
+    let Some(thread_id) = current_thread_id() else {
+        return false;
+    };
+    app_send
+        .send(UIServerCommand::RegisterProducerThread {
+            module_name: RString::from(module_name),
+            thread_id,
+        })
+        .is_ok()
+}
@@ -0,0 +1,182 @@
+use abi_stable::{
+    std_types::{RString, RVec},
+    type_layout::{TLData, TLField, TypeLayout},
+    StableAbi,
+};
+
+/// A field of a struct or of an enum variant
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+pub struct FieldSchema {
+    /// Field name, tuple fields are named by their index
+    pub name: RString,
+
+    /// Name of the type of the field, without generic parameters (`ROption<RString>` is `ROption`)
+    #[sabi(last_prefix_field)]
+    pub ty: RString,
+}
+
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+pub struct VariantSchema {
+    pub name: RString,
+
+    #[sabi(last_prefix_field)]
+    pub fields: RVec<FieldSchema>,
+}
+
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+pub enum TypeKind {
+    Struct {
+        fields: RVec<FieldSchema>,
+    },
+    Enum {
+        variants: RVec<VariantSchema>,
+    },
+    /// Primitives, opaque types and unions
+    Other,
+}
+
+/// Machine readable description of a type of the abi
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+pub struct TypeSchema {
+    pub name: RString,
+
+    #[sabi(last_prefix_field)]
+    pub kind: TypeKind,
+}
+
+impl TypeSchema {
+    /// Describe a type from its `abi_stable` layout
+    pub fn of<T: StableAbi>() -> Self {
+        Self::from_layout(T::LAYOUT)
+    }
+    pub fn from_layout(layout: &'static TypeLayout) -> Self {
+        let kind = match layout.data() {
+            TLData::Struct { fields } => TypeKind::Struct {
+                fields: fields.iter().map(field_schema).collect(),
+            },
+            TLData::PrefixType(prefix) => TypeKind::Struct {
+                fields: prefix.fields.iter().map(field_schema).collect(),
+            },
+            TLData::Enum(tl_enum) => {
+                let mut fields = tl_enum.fields.iter();
+                let variants = tl_enum
+                    .variant_names_iter()
+                    .zip(tl_enum.field_count.iter())
+                    .map(|(name, count)| VariantSchema {
+                        name: name.into(),
+                        fields: fields
+                            .by_ref()
+                            .take(*count as usize)
+                            .map(field_schema)
+                            .collect(),
+                    })
+                    .collect();
+                TypeKind::Enum { variants }
+            }
+            _ => TypeKind::Other,
+        };
+        Self {
+            name: layout.name().into(),
+            kind,
+        }
+    }
+    pub fn field(&self, name: &str) -> Option<&FieldSchema> {
+        match &self.kind {
+            TypeKind::Struct { fields } => fields.iter().find(|field| field.name == name),
+            _ => None,
+        }
+    }
+    pub fn variant(&self, name: &str) -> Option<&VariantSchema> {
+        match &self.kind {
+            TypeKind::Enum { variants } => variants.iter().find(|variant| variant.name == name),
+            _ => None,
+        }
+    }
+}
+
+fn field_schema(field: TLField) -> FieldSchema {
+    FieldSchema {
+        name: field.name().into(),
+        ty: field.full_type().to_string().trim().into(),
+    }
+}
+
+/// Add to `types` the schema of `layout` and of all the types of this crate it references,
+/// trough fields or generic parameters
+fn collect_schema(layout: &'static TypeLayout, types: &mut Vec<TypeSchema>) {
+    let is_own_type = layout.package() == env!("CARGO_PKG_NAME");
+    if is_own_type {
+        if types.iter().any(|schema| schema.name == layout.name()) {
+            return;
+        }
+        types.push(TypeSchema::from_layout(layout));
+    }
+    let fields = match layout.data() {
+        TLData::Struct { fields } | TLData::Union { fields } => Some(fields),
+        TLData::PrefixType(prefix) => Some(prefix.fields),
+        TLData::Enum(tl_enum) => Some(tl_enum.fields),
+        _ => None,
+    };
+    for field in fields.into_iter().flatten() {
+        collect_schema(field.layout(), types);
+    }
+    for param in layout.generics().type_params() {
+        collect_schema(param(), types);
+    }
+}
+
+macro_rules! schema_roots {
+    ($($ty:ty),* $(,)?) => {
+        /// Describe all the types that can be exchanged between the host and the modules,
+        /// starting from the commands and including every type of this crate they reference.
+        ///
+        /// This is generated from the `abi_stable` layouts, so it can't drift from the code,
+        /// external tooling (the IPC bridge, docs, fuzzers) should use it instead of hardcoding the protocol
+        pub fn schema() -> RVec<TypeSchema> {
+            let mut types = Vec::new();
+            $(collect_schema(<$ty as StableAbi>::LAYOUT, &mut types);)*
+            types.into()
+        }
+    };
+}
+
+schema_roots!(crate::module::UIServerCommand, crate::events::AppEvent);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_schema_command() {
+        let schema = schema();
+        let command = schema
+            .iter()
+            .find(|schema| schema.name == "UIServerCommand")
+            .unwrap();
+        let add = command.variant("AddActivity").unwrap();
+        let field_names: Vec<_> = add.fields.iter().map(|field| field.name.as_str()).collect();
+        assert_eq!(field_names, vec!["activity_id", "widget", "reply"]);
+        assert_eq!(add.fields[0].ty, "ActivityIdentifier");
+        let notification = command.variant("RequestNotification").unwrap();
+        assert_eq!(notification.fields[2].ty, "ROption");
+
+        let TypeKind::Enum { variants } = &command.kind else {
+            panic!("UIServerCommand is not an enum");
+        };
+        let TLData::Enum(tl_enum) = <crate::module::UIServerCommand as StableAbi>::LAYOUT.data()
+        else {
+            panic!("UIServerCommand layout is not an enum");
+        };
+        assert_eq!(variants.len(), tl_enum.variant_count());
+
+        // referenced trough fields and generic parameters
+        for name in ["ActivityIdentifier", "ActivityMetadata", "BrightnessDevice"] {
+            assert!(schema.iter().any(|schema| schema.name == name), "{name}");
+        }
+        assert!(!schema.iter().any(|schema| schema.name == "RString"));
+    }
+}
@@ -0,0 +1,63 @@
+//! Sounds played by the host, so the modules don't need to link an audio stack.
+
+use abi_stable::{std_types::RString, StableAbi};
+
+/// What to play
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SoundSource {
+    /// A sound from the freedesktop sound theme, like `message-new-instant` or `bell`
+    Event(RString),
+    /// Path of an audio file
+    File(RString),
+}
+
+/// A sound for `UIServerCommand::PlaySound`
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SoundSpec {
+    pub source: SoundSource,
+    /// From 0.0 (muted) to 1.0 (the volume of the output)
+    #[sabi(last_prefix_field)]
+    pub volume: f32,
+}
+
+impl SoundSpec {
+    /// A sound from the sound theme at full volume
+    pub fn event(name: &str) -> Self {
+        Self {
+            source: SoundSource::Event(name.into()),
+            volume: 1.0,
+        }
+    }
+    /// An audio file at full volume
+    pub fn file(path: &str) -> Self {
+        Self {
+            source: SoundSource::File(path.into()),
+            volume: 1.0,
+        }
+    }
+    /// Set the volume, clamped between 0.0 and 1.0
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = if volume.is_nan() {
+            1.0
+        } else {
+            volume.clamp(0.0, 1.0)
+        };
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_volume_clamped() {
+        assert_eq!(SoundSpec::event("bell").with_volume(3.0).volume, 1.0);
+        assert_eq!(SoundSpec::file("/tmp/a.ogg").with_volume(-1.0).volume, 0.0);
+        assert_eq!(SoundSpec::event("bell").with_volume(f32::NAN).volume, 1.0);
+    }
+}
@@ -0,0 +1,128 @@
+//! Modules compiled into the host, without a dynamic library.
+//!
+//! Distributions can build a single dynisland binary with all the modules,
+//! and developers can debug a module without loading it from a `.so`:
+//! the host registers the [`ModuleBuilderRef`] returned by [`leak_builder`]
+//! next to the ones loaded with `RootModule::load_from_file`, and everything else works the same.
+//!
+//! # Examples
+//! ```ignore
+//! let builder = leak_builder(ModuleBuilder::with_defaults(music_module::new, music_module::NAME));
+//! // or, to create an instance directly
+//! let module = from_boxed(Box::new(MusicModule::new(app_send)));
+//! ```
+
+use abi_stable::{
+    external_types::crossbeam_channel::RSender,
+    prefix_type::PrefixTypeTrait,
+    sabi_trait::TD_Opaque,
+    std_types::{RBoxError, ROption, RResult, RString, RVec},
+};
+
+use crate::{
+    config::ConfigError,
+    events::{
+        AppEvent, CompositorEvent, ConnectivityState, InteractionEvent, KeyEvent, ModuleServerEvent,
+    },
+    feedback::CommandError,
+    history::CommandHistory,
+    logger::SabiLogger,
+    module::{
+        ActivityIdentifier, ModuleBuilder, ModuleBuilderRef, ModuleType, SabiModule, SabiModule_TO,
+    },
+};
+
+/// Make a `ModuleBuilderRef` from a builder defined in the same binary
+pub fn leak_builder(builder: ModuleBuilder) -> ModuleBuilderRef {
+    builder.leak_into_prefix()
+}
+
+/// Make a `ModuleType` from a module created in the same binary
+pub fn from_boxed(module: Box<dyn SabiModule>) -> ModuleType {
+    SabiModule_TO::from_value(BoxedModule(module), TD_Opaque)
+}
+
+struct BoxedModule(Box<dyn SabiModule>);
+
+impl SabiModule for BoxedModule {
+    fn init(&self) {
+        self.0.init()
+    }
+    fn update_config(&mut self, config: RString) -> RResult<(), ConfigError> {
+        self.0.update_config(config)
+    }
+    fn restart_producers(&self) {
+        self.0.restart_producers()
+    }
+    fn default_config(&self) -> RResult<RString, RBoxError> {
+        self.0.default_config()
+    }
+    fn cli_command(&self, command: RString) -> RResult<RString, RBoxError> {
+        self.0.cli_command(command)
+    }
+    fn on_connectivity_changed(&self, state: ConnectivityState) {
+        self.0.on_connectivity_changed(state)
+    }
+    fn validate_config(&self, config: RString) -> RResult<(), ConfigError> {
+        self.0.validate_config(config)
+    }
+    fn on_compositor_event(&self, event: CompositorEvent) {
+        self.0.on_compositor_event(event)
+    }
+    fn on_activity_interaction(&self, id: ActivityIdentifier, action: InteractionEvent) {
+        self.0.on_activity_interaction(id, action)
+    }
+    fn set_logger(&self, logger: SabiLogger) {
+        self.0.set_logger(logger)
+    }
+    fn restore_state(&mut self, history: CommandHistory) {
+        self.0.restore_state(history)
+    }
+    fn on_app_event(&self, event: AppEvent) {
+        self.0.on_app_event(event)
+    }
+    fn export_effective_config(&self) -> RResult<RString, RBoxError> {
+        self.0.export_effective_config()
+    }
+    fn on_key_event(&self, id: ActivityIdentifier, event: KeyEvent) -> bool {
+        self.0.on_key_event(id, event)
+    }
+    fn on_module_message(&self, payload: RVec<u8>) -> ROption<RVec<u8>> {
+        self.0.on_module_message(payload)
+    }
+    fn on_timer(&self, token: u64) {
+        self.0.on_timer(token)
+    }
+    fn set_error_sender(&self, sender: RSender<CommandError>) {
+        self.0.set_error_sender(sender)
+    }
+    fn handle_event(&self, event: ModuleServerEvent) -> bool {
+        self.0.handle_event(event)
+    }
+    fn shutdown(&mut self) {
+        self.0.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct TestModule;
+    impl SabiModule for TestModule {
+        fn init(&self) {}
+        fn update_config(&mut self, _config: RString) -> RResult<(), ConfigError> {
+            RResult::ROk(())
+        }
+        fn restart_producers(&self) {}
+        fn cli_command(&self, command: RString) -> RResult<RString, RBoxError> {
+            RResult::ROk(command)
+        }
+    }
+
+    #[test]
+    fn test_from_boxed_forwards() {
+        let module = from_boxed(Box::new(TestModule));
+        assert_eq!(module.cli_command("ping".into()).unwrap(), "ping");
+    }
+}
@@ -0,0 +1,273 @@
+use abi_stable::{
+    std_types::{ROption, RString, RVec},
+    StableAbi,
+};
+
+use crate::geometry::{RectAbi, SizeAbi};
+
+/// Cursor to show while the pointer is over an activity
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CursorKind {
+    /// The cursor chosen by the LayoutManager
+    #[default]
+    Default,
+    Pointer,
+    Grab,
+    Grabbing,
+    Text,
+    Crosshair,
+    NotAllowed,
+    /// Any other CSS cursor name, see `gdk::Cursor::from_name`
+    Named(RString),
+}
+
+impl CursorKind {
+    /// CSS name of the cursor, `None` for [`CursorKind::Default`]
+    pub fn css_name(&self) -> Option<&str> {
+        match self {
+            CursorKind::Default => None,
+            CursorKind::Pointer => Some("pointer"),
+            CursorKind::Grab => Some("grab"),
+            CursorKind::Grabbing => Some("grabbing"),
+            CursorKind::Text => Some("text"),
+            CursorKind::Crosshair => Some("crosshair"),
+            CursorKind::NotAllowed => Some("not-allowed"),
+            CursorKind::Named(name) => Some(name),
+        }
+    }
+}
+
+/// Stacking layer of an activity, relative to the other activities
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverlayLayer {
+    /// Where the LayoutManager normally puts the activity
+    #[default]
+    Normal,
+    /// Above the other activities of the same window
+    Raised,
+    /// Above everything, like an OSD
+    Overlay,
+}
+
+/// Text of a tooltip
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TooltipText {
+    Plain(RString),
+    /// Pango markup, like `<b>Title</b>\nbody`
+    Markup(RString),
+}
+
+/// Tooltip shown by the host while the pointer is over an activity
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tooltip {
+    pub text: TooltipText,
+
+    /// Name of an icon from the icon theme, shown next to the text
+    #[sabi(last_prefix_field)]
+    pub icon: ROption<RString>,
+}
+
+impl Tooltip {
+    pub fn plain(text: &str) -> Self {
+        Self {
+            text: TooltipText::Plain(text.into()),
+            icon: ROption::RNone,
+        }
+    }
+    pub fn markup(markup: &str) -> Self {
+        Self {
+            text: TooltipText::Markup(markup.into()),
+            icon: ROption::RNone,
+        }
+    }
+    pub fn with_icon(mut self, icon_name: &str) -> Self {
+        self.icon = ROption::RSome(icon_name.into());
+        self
+    }
+    /// The text as pango markup, plain text is escaped
+    pub fn to_markup(&self) -> String {
+        match &self.text {
+            TooltipText::Plain(text) => escape_markup(text),
+            TooltipText::Markup(markup) => markup.to_string(),
+        }
+    }
+}
+
+/// Where a popup is placed relative to its activity
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PopupAnchor {
+    /// Below the activity, or above if there is no space
+    #[default]
+    Below,
+    Above,
+    Left,
+    Right,
+    /// Pointing at a rectangle, relative to the top left corner of the activity widget
+    Rect(RectAbi),
+}
+
+/// Monitor or host window an activity should be shown in
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputSelector {
+    /// A window of the host, like the `window_name` in the metadata
+    Window(RString),
+    /// A monitor by connector name, like `DP-1`
+    Monitor(RString),
+    /// The monitor with the focused window
+    Focused,
+    /// The primary monitor of the compositor
+    Primary,
+}
+
+/// Format of the data of an [`EncodedImage`]
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImageFormat {
+    #[default]
+    Png,
+}
+
+/// An image encoded in a file format
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncodedImage {
+    pub format: ImageFormat,
+    /// Size in pixels, including the scale factor of the monitor
+    pub size: SizeAbi,
+
+    #[sabi(last_prefix_field)]
+    pub data: RVec<u8>,
+}
+
+impl EncodedImage {
+    /// MIME type of the data, like `image/png`
+    pub fn mime_type(&self) -> &'static str {
+        match self.format {
+            ImageFormat::Png => "image/png",
+        }
+    }
+}
+
+/// Layer-shell layer of a window, from the bottom to the top
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowLayer {
+    Background,
+    Bottom,
+    Top,
+    Overlay,
+}
+
+/// Edges of the output a window is anchored to, with the same meaning as in gtk-layer-shell
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowAnchors(u32);
+
+impl WindowAnchors {
+    pub const NONE: Self = Self(0);
+    pub const TOP: Self = Self(1);
+    pub const BOTTOM: Self = Self(1 << 1);
+    pub const LEFT: Self = Self(1 << 2);
+    pub const RIGHT: Self = Self(1 << 3);
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for WindowAnchors {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Distance in pixels of a window from the anchored edges
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowMargins {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    #[sabi(last_prefix_field)]
+    pub left: i32,
+}
+
+/// Changes to the properties of a window, see `UIServerCommand::RequestWindowProperties`.
+///
+/// The properties set to `RNone` are not changed
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowProps {
+    pub layer: ROption<WindowLayer>,
+    pub anchors: ROption<WindowAnchors>,
+    #[sabi(last_prefix_field)]
+    pub margins: ROption<WindowMargins>,
+}
+
+impl WindowProps {
+    pub fn layer(mut self, layer: WindowLayer) -> Self {
+        self.layer = ROption::RSome(layer);
+        self
+    }
+    pub fn anchors(mut self, anchors: WindowAnchors) -> Self {
+        self.anchors = ROption::RSome(anchors);
+        self
+    }
+    pub fn margins(mut self, margins: WindowMargins) -> Self {
+        self.margins = ROption::RSome(margins);
+        self
+    }
+    /// Apply the properties set in `other` over these
+    pub fn merge(&mut self, other: &WindowProps) {
+        if let ROption::RSome(layer) = other.layer {
+            self.layer = ROption::RSome(layer);
+        }
+        if let ROption::RSome(anchors) = other.anchors {
+            self.anchors = ROption::RSome(anchors);
+        }
+        if let ROption::RSome(margins) = other.margins {
+            self.margins = ROption::RSome(margins);
+        }
+    }
+}
+
+/// Escape the characters that have a special meaning in pango markup
+pub fn escape_markup(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
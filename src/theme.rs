@@ -0,0 +1,85 @@
+//! Resolved appearance of the system, so modules drawing their own content can match it.
+
+use abi_stable::{std_types::RString, StableAbi};
+
+/// A color with components from 0.0 to 1.0
+#[repr(C)]
+#[derive(StableAbi, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorAbi {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+
+    #[sabi(last_prefix_field)]
+    pub alpha: f32,
+}
+
+impl ColorAbi {
+    pub const fn new(red: f32, green: f32, blue: f32, alpha: f32) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+    /// The color in css format, like `rgba(53,132,228,1)`
+    pub fn to_css(&self) -> String {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        format!(
+            "rgba({},{},{},{})",
+            channel(self.red),
+            channel(self.green),
+            channel(self.blue),
+            self.alpha.clamp(0.0, 1.0)
+        )
+    }
+}
+
+/// The current theme, sent with `ModuleServerEvent::ThemeChanged`
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThemeInfo {
+    /// The user prefers a dark appearance
+    pub dark: bool,
+    pub accent_color: ColorAbi,
+    /// Pango font description of the interface font, like `Cantarell 11`
+    pub font: RString,
+
+    /// Scale factor of the monitors, 1.0 without scaling
+    #[sabi(last_prefix_field)]
+    pub scale_factor: f64,
+}
+
+#[cfg(feature = "gtk")]
+mod gtk_conversions {
+    use super::ColorAbi;
+
+    impl From<gdk::RGBA> for ColorAbi {
+        fn from(color: gdk::RGBA) -> Self {
+            ColorAbi::new(color.red(), color.green(), color.blue(), color.alpha())
+        }
+    }
+    impl From<ColorAbi> for gdk::RGBA {
+        fn from(color: ColorAbi) -> Self {
+            gdk::RGBA::new(color.red, color.green, color.blue, color.alpha)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_color_css() {
+        let accent = ColorAbi::new(0.208, 0.518, 0.894, 1.0);
+        assert_eq!(accent.to_css(), "rgba(53,132,228,1)");
+        assert_eq!(
+            ColorAbi::new(2.0, -1.0, 0.0, 0.5).to_css(),
+            "rgba(255,0,0,0.5)"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use std::{fmt::Display, str::FromStr};
+
+/// A `major.minor.patch` version, pre-release and build metadata are ignored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = VersionError;
+
+    /// Parse `1`, `1.2` or `1.2.3`, optionally followed by `-pre` or `+build`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let core = s.trim().split(['-', '+']).next().unwrap_or_default();
+        let mut parts = core.split('.').map(|part| part.parse::<u64>());
+        let invalid = || VersionError::Invalid(s.to_string());
+        let major = parts.next().ok_or_else(invalid)?.map_err(|_| invalid())?;
+        let minor = parts.next().unwrap_or(Ok(0)).map_err(|_| invalid())?;
+        let patch = parts.next().unwrap_or(Ok(0)).map_err(|_| invalid())?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self::new(major, minor, patch))
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionError {
+    /// The string is not a valid version
+    Invalid(String),
+    /// The available version is older than the required one
+    TooOld { required: Version, found: Version },
+}
+
+impl Display for VersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionError::Invalid(version) => write!(f, "invalid version: {version:?}"),
+            VersionError::TooOld { required, found } => {
+                write!(f, "version {required} or newer is required, found {found}")
+            }
+        }
+    }
+}
+impl std::error::Error for VersionError {}
+
+/// Check that `found` is at least `required`, an empty `required` is always satisfied
+pub fn check_min_version(required: &str, found: &str) -> Result<(), VersionError> {
+    if required.trim().is_empty() {
+        return Ok(());
+    }
+    let required: Version = required.parse()?;
+    let found: Version = found.parse()?;
+    if found < required {
+        return Err(VersionError::TooOld { required, found });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_version_parse() {
+        assert_eq!("1.2.3".parse(), Ok(Version::new(1, 2, 3)));
+        assert_eq!("0.1".parse(), Ok(Version::new(0, 1, 0)));
+        assert_eq!("2.0.0-beta.1".parse(), Ok(Version::new(2, 0, 0)));
+        assert!("1.x".parse::<Version>().is_err());
+        assert!("".parse::<Version>().is_err());
+        assert!("1.2.3.4".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn test_check_min_version() {
+        assert!(check_min_version("", "0.1.0").is_ok());
+        assert!(check_min_version("0.2.0", "0.2.1").is_ok());
+        assert_eq!(
+            check_min_version("0.3", "0.2.1"),
+            Err(VersionError::TooOld {
+                required: Version::new(0, 3, 0),
+                found: Version::new(0, 2, 1)
+            })
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Policy for web content embedded in activity widgets.
+//!
+//! A module that embeds a web view (like a `webkit6::WebView`) in its widget can attach a
+//! [`WebContentPolicy`] to the `ActivityMetadata` of the activity.
+//!
+//! Hosts that embed such widgets must:
+//! - reject every navigation (including redirects and subframes) whose origin is not allowed by [`WebContentPolicy::allows`]
+//! - open the rejected top level navigations in the default browser if `open_blocked_externally` is set, drop them otherwise
+//! - disable javascript unless `allow_javascript` is set
+//! - never create new windows unless `allow_new_windows` is set
+//!
+//! The default policy is the most restrictive one, it doesn't allow any origin.
+
+use abi_stable::{
+    std_types::{RString, RVec},
+    StableAbi,
+};
+
+/// Navigation allowlist and permissions for the web content of an activity
+#[repr(C)]
+#[derive(StableAbi, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WebContentPolicy {
+    /// Origins the content can navigate to, like `https://example.com` or `https://*.example.com`.
+    ///
+    /// `*.` matches any subdomain but not the domain itself, a missing port matches only the default port of the scheme
+    pub allowed_origins: RVec<RString>,
+    pub allow_javascript: bool,
+    /// Allow `window.open` and `target="_blank"` links
+    pub allow_new_windows: bool,
+
+    /// Open the blocked navigations in the default browser instead of dropping them
+    #[sabi(last_prefix_field)]
+    pub open_blocked_externally: bool,
+}
+
+impl WebContentPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn allow_origin(mut self, origin: &str) -> Self {
+        self.allowed_origins.push(origin.into());
+        self
+    }
+    pub fn allow_javascript(mut self, allow: bool) -> Self {
+        self.allow_javascript = allow;
+        self
+    }
+    pub fn allow_new_windows(mut self, allow: bool) -> Self {
+        self.allow_new_windows = allow;
+        self
+    }
+    pub fn open_blocked_externally(mut self, open: bool) -> Self {
+        self.open_blocked_externally = open;
+        self
+    }
+
+    /// Whether the content can navigate to `uri`, uris that don't have an authority (like `data:` or `file:`)
+    /// are never allowed
+    pub fn allows(&self, uri: &str) -> bool {
+        let Some(origin) = Origin::parse(uri) else {
+            return false;
+        };
+        self.allowed_origins
+            .iter()
+            .filter_map(|pattern| Origin::parse(pattern))
+            .any(|pattern| pattern.matches(&origin))
+    }
+}
+
+struct Origin {
+    scheme: String,
+    host: String,
+    port: Option<u16>,
+}
+
+impl Origin {
+    fn parse(uri: &str) -> Option<Self> {
+        // the web view strips these before parsing, so they could hide the real authority
+        if uri.contains(['\t', '\r', '\n']) {
+            return None;
+        }
+        let (scheme, rest) = uri.split_once("://")?;
+        let scheme = scheme.to_ascii_lowercase();
+        // like the web view, treat `\` as `/` for the special schemes
+        let special = matches!(
+            scheme.as_str(),
+            "http" | "https" | "ws" | "wss" | "ftp" | "file"
+        );
+        let authority = rest
+            .split(|c| matches!(c, '/' | '?' | '#') || (special && c == '\\'))
+            .next()
+            .unwrap_or_default();
+        let authority = authority.rsplit_once('@').map_or(authority, |(_, a)| a);
+        // ipv6 addresses are enclosed in brackets, like `[::1]:8080`
+        let split_at = match authority.find(']') {
+            Some(end) => end + 1,
+            None => authority.rfind(':').unwrap_or(authority.len()),
+        };
+        let (host, port) = authority.split_at(split_at);
+        let port = match port.strip_prefix(':') {
+            Some(port) => Some(port.parse::<u16>().ok()?),
+            None if port.is_empty() => None,
+            None => return None,
+        };
+        if host.is_empty() {
+            return None;
+        }
+        let port = match (scheme.as_str(), port) {
+            ("http", Some(80)) | ("https", Some(443)) => None,
+            (_, port) => port,
+        };
+        Some(Self {
+            scheme,
+            host: host.to_ascii_lowercase(),
+            port,
+        })
+    }
+
+    fn matches(&self, origin: &Origin) -> bool {
+        if self.scheme != origin.scheme || self.port != origin.port {
+            return false;
+        }
+        match self.host.strip_prefix("*.") {
+            Some(domain) => origin
+                .host
+                .strip_suffix(domain)
+                .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+            None => self.host == origin.host,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_web_content_policy_allows() {
+        let policy = WebContentPolicy::new()
+            .allow_origin("https://example.com")
+            .allow_origin("https://*.cdn.example.com")
+            .allow_origin("http://localhost:8080");
+        assert!(policy.allows("https://example.com/path?q=1"));
+        assert!(policy.allows("https://EXAMPLE.com:443"));
+        assert!(policy.allows("https://user@example.com/"));
+        assert!(policy.allows("https://a.cdn.example.com/x.js"));
+        assert!(!policy.allows("https://cdn.example.com/x.js"));
+        assert!(!policy.allows("https://evilcdn.example.com/"));
+        assert!(!policy.allows("http://example.com/"));
+        assert!(!policy.allows("https://example.com:8443/"));
+        assert!(!policy.allows("https://example.com.evil.org/"));
+        assert!(!policy.allows("https://evil.com\\@example.com/"));
+        assert!(!policy.allows("https://evil.com\t@example.com/"));
+        assert!(policy.allows("http://localhost:8080/"));
+        assert!(!policy.allows("http://localhost/"));
+        assert!(!policy.allows("data:text/html,hi"));
+        assert!(WebContentPolicy::new()
+            .allow_origin("http://[::1]")
+            .allows("http://[::1]:80/"));
+        assert!(!WebContentPolicy::default().allows("https://example.com"));
+    }
+}